@@ -0,0 +1,210 @@
+use std::path::Path;
+
+use tauri::State;
+
+use crate::{
+    database::{with_connection, AppState},
+    error::{AppError, AppResult},
+    media_store::{self, MediaStore},
+    models::{
+        Asset, AssetGcInput, AssetGcReport, CreateAssetInput, ImportAssetInput, UpdateAssetInput,
+    },
+    repository::{assets_repo, scenes_repo},
+};
+
+fn require_non_empty(field: &str, value: &str) -> AppResult<()> {
+    if value.trim().is_empty() {
+        return Err(AppError::validation(
+            field,
+            format!("{field} cannot be empty"),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_asset_type(asset_type: &str) -> AppResult<()> {
+    if !assets_repo::ASSET_TYPES.contains(&asset_type) {
+        return Err(AppError::validation(
+            "asset_type",
+            format!(
+                "asset_type must be one of: {}",
+                assets_repo::ASSET_TYPES.join(", ")
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn require_stored_path(media_store: &MediaStore, local_path: &str) -> AppResult<()> {
+    if !media_store.contains(local_path) {
+        return Err(AppError::validation(
+            "local_path",
+            "local_path must reference a file in the media store; use import_asset",
+        ));
+    }
+    Ok(())
+}
+
+fn require_scene_in_project(
+    conn: &rusqlite::Connection,
+    scene_id: &str,
+    project_id: &str,
+) -> AppResult<()> {
+    match scenes_repo::get_by_id(conn, scene_id).map_err(AppError::from)? {
+        Some(scene) if scene.project_id == project_id => Ok(()),
+        Some(_) => Err(AppError::validation(
+            "scene_id",
+            "scene_id belongs to a different project",
+        )),
+        None => Err(AppError::not_found("scene", scene_id)),
+    }
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn create_asset(state: State<'_, AppState>, input: CreateAssetInput) -> AppResult<Asset> {
+    require_non_empty("project_id", &input.project_id)?;
+    validate_asset_type(&input.asset_type)?;
+    if let Some(ref status) = input.status {
+        require_non_empty("status", status)?;
+    }
+    if let Some(ref local_path) = input.local_path {
+        require_stored_path(&state.media_store, local_path)?;
+    }
+    with_connection(&state, |conn| {
+        if let Some(ref scene_id) = input.scene_id {
+            require_scene_in_project(conn, scene_id, &input.project_id)?;
+        }
+        assets_repo::create(conn, input).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn import_asset(state: State<'_, AppState>, input: ImportAssetInput) -> AppResult<Asset> {
+    require_non_empty("project_id", &input.project_id)?;
+    require_non_empty("source_path", &input.source_path)?;
+    validate_asset_type(&input.asset_type)?;
+    let source = Path::new(&input.source_path);
+    if !source.is_file() {
+        return Err(AppError::validation(
+            "source_path",
+            format!("{} is not a file", input.source_path),
+        ));
+    }
+
+    let stored = state
+        .media_store
+        .import_file(source)
+        .map_err(|e| AppError::io(format_args!("failed to import {}", input.source_path), e))?;
+
+    with_connection(&state, |conn| {
+        if let Some(ref scene_id) = input.scene_id {
+            require_scene_in_project(conn, scene_id, &input.project_id)?;
+        }
+        assets_repo::create(
+            conn,
+            CreateAssetInput {
+                project_id: input.project_id,
+                scene_id: input.scene_id,
+                task_id: None,
+                asset_type: input.asset_type,
+                provider: input.provider,
+                local_path: Some(stored.relative_path),
+                meta_json: input.meta_json,
+                checksum: Some(stored.checksum),
+                status: Some("ready".into()),
+            },
+        )
+        .map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_assets(state: State<'_, AppState>, project_id: String) -> AppResult<Vec<Asset>> {
+    require_non_empty("project_id", &project_id)?;
+    with_connection(&state, |conn| {
+        assets_repo::list_by_project(conn, &project_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_scene_assets(state: State<'_, AppState>, scene_id: String) -> AppResult<Vec<Asset>> {
+    require_non_empty("scene_id", &scene_id)?;
+    with_connection(&state, |conn| {
+        assets_repo::list_by_scene(conn, &scene_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_asset(state: State<'_, AppState>, asset_id: String) -> AppResult<Option<Asset>> {
+    require_non_empty("asset_id", &asset_id)?;
+    with_connection(&state, |conn| {
+        assets_repo::get_by_id(conn, &asset_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn update_asset(
+    state: State<'_, AppState>,
+    asset_id: String,
+    input: UpdateAssetInput,
+) -> AppResult<Option<Asset>> {
+    require_non_empty("asset_id", &asset_id)?;
+    if let Some(ref status) = input.status {
+        require_non_empty("status", status)?;
+    }
+    if let Some(ref local_path) = input.local_path {
+        require_stored_path(&state.media_store, local_path)?;
+    }
+    with_connection(&state, |conn| {
+        if let Some(ref scene_id) = input.scene_id {
+            let Some(asset) = assets_repo::get_by_id(conn, &asset_id).map_err(AppError::from)?
+            else {
+                return Ok(None);
+            };
+            require_scene_in_project(conn, scene_id, &asset.project_id)?;
+        }
+        assets_repo::update(conn, &asset_id, input).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn delete_asset(state: State<'_, AppState>, asset_id: String) -> AppResult<bool> {
+    require_non_empty("asset_id", &asset_id)?;
+    with_connection(&state, |conn| {
+        assets_repo::delete(conn, &asset_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn resolve_asset_path(
+    state: State<'_, AppState>,
+    asset_id: String,
+) -> AppResult<Option<String>> {
+    require_non_empty("asset_id", &asset_id)?;
+    let asset = with_connection(&state, |conn| {
+        assets_repo::get_by_id(conn, &asset_id).map_err(AppError::from)
+    })?;
+    Ok(asset
+        .and_then(|a| a.local_path)
+        .and_then(|path| state.media_store.resolve(&path))
+        .map(|path| path.to_string_lossy().into_owned()))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn collect_asset_garbage(
+    state: State<'_, AppState>,
+    input: AssetGcInput,
+) -> AppResult<AssetGcReport> {
+    with_connection(&state, |conn| {
+        media_store::collect_garbage(conn, &state.media_store, &input)
+    })
+}
@@ -0,0 +1,42 @@
+use std::path::Path;
+
+use tauri::{AppHandle, Runtime, State};
+
+use crate::{
+    database::{with_connection, AppState},
+    diagnostics,
+    error::{AppError, AppResult},
+    logging,
+    models::DiagnosticsExportReport,
+    worker::manager::WorkerManager,
+};
+
+fn require_non_empty(field: &str, value: &str) -> AppResult<()> {
+    if value.trim().is_empty() {
+        return Err(AppError::validation(
+            field,
+            format!("{field} cannot be empty"),
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn export_diagnostics<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    dest_path: String,
+) -> AppResult<DiagnosticsExportReport> {
+    require_non_empty("dest_path", &dest_path)?;
+    let worker = WorkerManager::diagnostics(&state.worker_manager)?;
+    let database = with_connection(&state, |conn| Ok(diagnostics::check_database(conn)))?;
+    let (log_dir, log_files) = logging::recent_log_files(&app).map_err(AppError::internal)?;
+    diagnostics::write_bundle(
+        &worker,
+        &database,
+        &log_dir,
+        &log_files,
+        Path::new(&dest_path),
+    )
+}
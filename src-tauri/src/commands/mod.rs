@@ -1,4 +1,7 @@
+pub mod asset_commands;
+pub mod diagnostics_commands;
 pub mod project_commands;
 pub mod scene_commands;
 pub mod script_commands;
+pub mod template_commands;
 pub mod worker_commands;
@@ -1,60 +1,134 @@
+use std::path::Path;
+
 use tauri::State;
 
 use crate::{
     database::{with_connection, AppState},
-    models::{CreateProjectInput, Project, UpdateProjectInput},
-    repository::projects_repo,
+    error::{AppError, AppResult},
+    media_store,
+    models::{
+        CreateProjectInput, DuplicateProjectInput, Project, ProjectExportReport, UpdateProjectInput,
+    },
+    project_bundle,
+    repository::{assets_repo, projects_repo},
 };
 
-fn require_non_empty(field: &str, value: &str) -> Result<(), String> {
+fn require_non_empty(field: &str, value: &str) -> AppResult<()> {
     if value.trim().is_empty() {
-        return Err(format!("{field} cannot be empty"));
+        return Err(AppError::validation(
+            field,
+            format!("{field} cannot be empty"),
+        ));
     }
     Ok(())
 }
 
 #[tauri::command]
-pub fn create_project(state: State<'_, AppState>, input: CreateProjectInput) -> Result<Project, String> {
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn create_project(state: State<'_, AppState>, input: CreateProjectInput) -> AppResult<Project> {
     require_non_empty("name", &input.name)?;
     with_connection(&state, |conn| {
-        projects_repo::create(conn, input).map_err(|e| e.to_string())
+        projects_repo::create(conn, input).map_err(AppError::from)
     })
 }
 
 #[tauri::command]
-pub fn list_projects(state: State<'_, AppState>) -> Result<Vec<Project>, String> {
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_projects(state: State<'_, AppState>) -> AppResult<Vec<Project>> {
     with_connection(&state, |conn| {
-        projects_repo::list_all(conn).map_err(|e| e.to_string())
+        projects_repo::list_all(conn).map_err(AppError::from)
     })
 }
 
 #[tauri::command]
-pub fn get_project(state: State<'_, AppState>, project_id: String) -> Result<Option<Project>, String> {
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_project(state: State<'_, AppState>, project_id: String) -> AppResult<Option<Project>> {
     require_non_empty("project_id", &project_id)?;
     with_connection(&state, |conn| {
-        projects_repo::get_by_id(conn, &project_id).map_err(|e| e.to_string())
+        projects_repo::get_by_id(conn, &project_id).map_err(AppError::from)
     })
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
 pub fn update_project(
     state: State<'_, AppState>,
     project_id: String,
     input: UpdateProjectInput,
-) -> Result<Option<Project>, String> {
+) -> AppResult<Option<Project>> {
     require_non_empty("project_id", &project_id)?;
     if let Some(ref name) = input.name {
         require_non_empty("name", name)?;
     }
     with_connection(&state, |conn| {
-        projects_repo::update(conn, &project_id, input).map_err(|e| e.to_string())
+        projects_repo::update(conn, &project_id, input).map_err(AppError::from)
     })
 }
 
 #[tauri::command]
-pub fn delete_project(state: State<'_, AppState>, project_id: String) -> Result<bool, String> {
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn delete_project(state: State<'_, AppState>, project_id: String) -> AppResult<bool> {
     require_non_empty("project_id", &project_id)?;
     with_connection(&state, |conn| {
-        projects_repo::delete(conn, &project_id).map_err(|e| e.to_string())
+        projects_repo::delete(conn, &project_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn duplicate_project(
+    state: State<'_, AppState>,
+    project_id: String,
+    input: DuplicateProjectInput,
+) -> AppResult<Option<Project>> {
+    require_non_empty("project_id", &project_id)?;
+    if let Some(ref name) = input.name {
+        require_non_empty("name", name)?;
+    }
+    let assets = if input.share_assets {
+        with_connection(&state, |conn| {
+            assets_repo::list_by_project(conn, &project_id).map_err(AppError::from)
+        })?
+    } else {
+        Vec::new()
+    };
+    // Hashing files can take a while; copy assets still outside the media
+    // store into it without holding the database lock.
+    let adopted = media_store::import_external_files(&state.media_store, &assets);
+    with_connection(&state, |conn| {
+        project_bundle::duplicate_project(conn, &project_id, input, &adopted)
+            .map_err(AppError::from)
     })
 }
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn export_project(
+    state: State<'_, AppState>,
+    project_id: String,
+    dest_path: String,
+) -> AppResult<ProjectExportReport> {
+    require_non_empty("project_id", &project_id)?;
+    require_non_empty("dest_path", &dest_path)?;
+    let snapshot = with_connection(&state, |conn| {
+        project_bundle::load_project(conn, &project_id).map_err(AppError::from)
+    })?
+    .ok_or_else(|| AppError::not_found("project", &project_id))?;
+    // Media can be large; write the archive without holding the database lock.
+    project_bundle::write_bundle(&state.media_store, &snapshot, Path::new(&dest_path))
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn import_project(state: State<'_, AppState>, bundle_path: String) -> AppResult<Project> {
+    require_non_empty("bundle_path", &bundle_path)?;
+    let source = Path::new(&bundle_path);
+    if !source.is_file() {
+        return Err(AppError::validation(
+            "bundle_path",
+            format!("{bundle_path} is not a file"),
+        ));
+    }
+    let bundle = project_bundle::read_bundle(&state.media_store, source)?;
+    with_connection(&state, |conn| project_bundle::restore_project(conn, bundle))
+}
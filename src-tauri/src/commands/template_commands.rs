@@ -0,0 +1,80 @@
+use tauri::State;
+
+use crate::{
+    database::{with_connection, AppState},
+    error::{AppError, AppResult},
+    models::{CreateProjectFromTemplateInput, Project, ProjectTemplate, SaveProjectTemplateInput},
+    repository::templates_repo,
+};
+
+fn require_non_empty(field: &str, value: &str) -> AppResult<()> {
+    if value.trim().is_empty() {
+        return Err(AppError::validation(
+            field,
+            format!("{field} cannot be empty"),
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn save_project_as_template(
+    state: State<'_, AppState>,
+    input: SaveProjectTemplateInput,
+) -> AppResult<Option<ProjectTemplate>> {
+    require_non_empty("project_id", &input.project_id)?;
+    require_non_empty("name", &input.name)?;
+    with_connection(&state, |conn| {
+        templates_repo::create_from_project(conn, input).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_project_templates(state: State<'_, AppState>) -> AppResult<Vec<ProjectTemplate>> {
+    with_connection(&state, |conn| {
+        templates_repo::list_all(conn).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_project_template(
+    state: State<'_, AppState>,
+    template_id: String,
+) -> AppResult<Option<ProjectTemplate>> {
+    require_non_empty("template_id", &template_id)?;
+    with_connection(&state, |conn| {
+        templates_repo::get_by_id(conn, &template_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn create_project_from_template(
+    state: State<'_, AppState>,
+    input: CreateProjectFromTemplateInput,
+) -> AppResult<Option<Project>> {
+    require_non_empty("template_id", &input.template_id)?;
+    require_non_empty("name", &input.name)?;
+    with_connection(&state, |conn| {
+        templates_repo::instantiate(conn, input).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn delete_project_template(state: State<'_, AppState>, template_id: String) -> AppResult<bool> {
+    require_non_empty("template_id", &template_id)?;
+    with_connection(&state, |conn| {
+        match templates_repo::get_by_id(conn, &template_id).map_err(AppError::from)? {
+            Some(template) if template.is_builtin => Err(AppError::validation(
+                "template_id",
+                "built-in templates cannot be deleted",
+            )),
+            Some(_) => templates_repo::delete(conn, &template_id).map_err(AppError::from),
+            None => Ok(false),
+        }
+    })
+}
@@ -1,53 +1,403 @@
+use std::{collections::HashSet, path::Path};
+
 use tauri::{AppHandle, Runtime, State};
 
 use crate::{
-    database::AppState,
+    database::{with_connection, AppState},
+    error::{AppError, AppResult},
+    models::{
+        ConcurrencyLimits, TaskGraphStatus, TaskInfo, TaskPriority, TaskRetryPolicy, TaskTimeout,
+        TaskTimeouts, WorkerLaunchConfig, WorkerPoolConfig, WorkerProgram, WorkerRestartPolicy,
+    },
+    repository::{retry_policies_repo, task_graphs_repo, tasks_repo},
     worker::{
-        dispatcher::{SubmitTaskInput, TaskInfo},
+        dispatcher::{SubmitTaskGraphInput, SubmitTaskInput},
         manager::{WorkerManager, WorkerStatus},
     },
 };
 
 #[tauri::command]
-pub fn get_worker_status(state: State<'_, AppState>) -> Result<WorkerStatus, String> {
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_worker_status(state: State<'_, AppState>) -> AppResult<WorkerStatus> {
     WorkerManager::get_status(&state.worker_manager)
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
 pub fn submit_task<R: Runtime + 'static>(
     app: AppHandle<R>,
     state: State<'_, AppState>,
     input: SubmitTaskInput,
-) -> Result<String, String> {
+) -> AppResult<String> {
     WorkerManager::submit_task(&state.worker_manager, &app, input)
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
 pub fn cancel_task<R: Runtime>(
     app: AppHandle<R>,
     state: State<'_, AppState>,
     task_id: String,
-) -> Result<bool, String> {
+) -> AppResult<bool> {
     WorkerManager::cancel_task(&state.worker_manager, &app, &task_id)
 }
 
 #[tauri::command]
-pub fn list_tasks(state: State<'_, AppState>) -> Result<Vec<TaskInfo>, String> {
-    WorkerManager::list_tasks(&state.worker_manager)
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn submit_task_graph<R: Runtime + 'static>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    input: SubmitTaskGraphInput,
+) -> AppResult<TaskGraphStatus> {
+    WorkerManager::submit_task_graph(&state.worker_manager, &app, input)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn cancel_task_graph<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    graph_id: String,
+) -> AppResult<bool> {
+    WorkerManager::cancel_task_graph(&state.worker_manager, &app, &graph_id)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_task_graph(
+    state: State<'_, AppState>,
+    graph_id: String,
+) -> AppResult<Option<TaskGraphStatus>> {
+    with_connection(&state, |conn| {
+        task_graphs_repo::get_status(conn, &graph_id).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_task_graphs(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> AppResult<Vec<TaskGraphStatus>> {
+    with_connection(&state, |conn| {
+        task_graphs_repo::list_by_project(conn, &project_id).map_err(AppError::from)
+    })
 }
 
 #[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn reprioritize_task<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    task_id: String,
+    priority: TaskPriority,
+) -> AppResult<TaskInfo> {
+    WorkerManager::reprioritize_task(&state.worker_manager, &app, &task_id, priority)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_tasks(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+) -> AppResult<Vec<TaskInfo>> {
+    with_connection(&state, |conn| {
+        match project_id.as_deref().map(str::trim).filter(|id| !id.is_empty()) {
+            Some(project_id) => tasks_repo::list_by_project(conn, project_id),
+            None => tasks_repo::list_all(conn),
+        }
+        .map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
 pub fn start_worker<R: Runtime + 'static>(
     app: AppHandle<R>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> AppResult<()> {
     WorkerManager::start(&state.worker_manager, &app)
 }
 
 #[tauri::command]
-pub fn stop_worker<R: Runtime>(
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn stop_worker<R: Runtime>(app: AppHandle<R>, state: State<'_, AppState>) -> AppResult<()> {
+    WorkerManager::stop(&state.worker_manager, &app)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn reset_worker_circuit<R: Runtime>(
     app: AppHandle<R>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    WorkerManager::stop(&state.worker_manager, &app)
+) -> AppResult<bool> {
+    WorkerManager::reset_circuit(&state.worker_manager, &app)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn list_retry_policies(state: State<'_, AppState>) -> AppResult<Vec<TaskRetryPolicy>> {
+    with_connection(&state, |conn| {
+        retry_policies_repo::list_all(conn).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_retry_policy(state: State<'_, AppState>, input: TaskRetryPolicy) -> AppResult<()> {
+    if input.task_type.trim().is_empty() {
+        return Err(AppError::validation(
+            "task_type",
+            "task_type cannot be empty",
+        ));
+    }
+    if input.policy.max_attempts == 0 {
+        return Err(AppError::validation(
+            "max_attempts",
+            "max_attempts must be >= 1",
+        ));
+    }
+    if input.policy.max_backoff_ms < input.policy.initial_backoff_ms {
+        return Err(AppError::validation(
+            "max_backoff_ms",
+            "max_backoff_ms must be >= initial_backoff_ms",
+        ));
+    }
+    with_connection(&state, |conn| {
+        retry_policies_repo::upsert(conn, &input).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn delete_retry_policy(state: State<'_, AppState>, task_type: String) -> AppResult<bool> {
+    with_connection(&state, |conn| {
+        retry_policies_repo::delete(conn, &task_type).map_err(AppError::from)
+    })
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_concurrency_limits(state: State<'_, AppState>) -> AppResult<ConcurrencyLimits> {
+    WorkerManager::get_concurrency_limits(&state.worker_manager)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_concurrency_limits<R: Runtime>(
+    app: AppHandle<R>,
+    state: State<'_, AppState>,
+    limits: ConcurrencyLimits,
+) -> AppResult<()> {
+    if limits.max_concurrent == 0 {
+        return Err(AppError::validation(
+            "max_concurrent",
+            "max_concurrent must be >= 1",
+        ));
+    }
+    if let Some((task_type, _)) = limits.per_task_type.iter().find(|(_, limit)| **limit == 0) {
+        return Err(AppError::validation(
+            "per_task_type",
+            format!("limit for task type {task_type} must be >= 1"),
+        ));
+    }
+    WorkerManager::set_concurrency_limits(&state.worker_manager, &app, limits)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_task_timeouts(state: State<'_, AppState>) -> AppResult<TaskTimeouts> {
+    WorkerManager::get_task_timeouts(&state.worker_manager)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_task_timeouts(state: State<'_, AppState>, timeouts: TaskTimeouts) -> AppResult<()> {
+    validate_timeout("default", &timeouts.default)?;
+    for (task_type, timeout) in &timeouts.per_task_type {
+        validate_timeout(task_type, timeout)?;
+    }
+    WorkerManager::set_task_timeouts(&state.worker_manager, timeouts)
+}
+
+fn validate_timeout(scope: &str, timeout: &TaskTimeout) -> AppResult<()> {
+    if timeout.max_runtime_secs == Some(0) || timeout.stall_secs == Some(0) {
+        return Err(AppError::validation(
+            "timeouts",
+            format!("{scope} timeouts must be at least 1 second"),
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_worker_launch_config(state: State<'_, AppState>) -> AppResult<WorkerLaunchConfig> {
+    WorkerManager::get_launch_config(&state.worker_manager)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_worker_launch_config(
+    state: State<'_, AppState>,
+    config: WorkerLaunchConfig,
+) -> AppResult<()> {
+    validate_launch_config(&config)?;
+    WorkerManager::set_launch_config(&state.worker_manager, config)
+}
+
+fn validate_launch_config(config: &WorkerLaunchConfig) -> AppResult<()> {
+    match &config.program {
+        WorkerProgram::Sidecar { name } if name.trim().is_empty() => {
+            return Err(AppError::validation(
+                "program",
+                "sidecar name cannot be empty",
+            ));
+        }
+        WorkerProgram::Executable { path } if !Path::new(path).is_file() => {
+            return Err(AppError::validation(
+                "program",
+                format!("{path} is not an executable file"),
+            ));
+        }
+        _ => {}
+    }
+    if let Some(key) = config
+        .env
+        .keys()
+        .find(|key| key.is_empty() || key.contains(['=', '\0']))
+    {
+        return Err(AppError::validation(
+            "env",
+            format!("invalid environment variable name {key:?}"),
+        ));
+    }
+    if let Some(dir) = &config.working_dir {
+        if !Path::new(dir).is_dir() {
+            return Err(AppError::validation(
+                "working_dir",
+                format!("{dir} is not a directory"),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// More workers than this would mostly contend for the same GPU.
+const MAX_POOL_WORKERS: u32 = 16;
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_worker_pool_config(state: State<'_, AppState>) -> AppResult<WorkerPoolConfig> {
+    WorkerManager::get_pool_config(&state.worker_manager)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_worker_pool_config(
+    state: State<'_, AppState>,
+    config: WorkerPoolConfig,
+) -> AppResult<()> {
+    validate_pool_config(&config)?;
+    WorkerManager::set_pool_config(&state.worker_manager, config)
+}
+
+fn validate_pool_config(config: &WorkerPoolConfig) -> AppResult<()> {
+    if config.size == 0 {
+        return Err(AppError::validation("size", "size must be >= 1"));
+    }
+    let mut names = HashSet::new();
+    let mut routed = HashSet::new();
+    for group in &config.groups {
+        if group.name.trim().is_empty() {
+            return Err(AppError::validation("groups", "group name cannot be empty"));
+        }
+        if !names.insert(group.name.as_str()) {
+            return Err(AppError::validation(
+                "groups",
+                format!("duplicate group {}", group.name),
+            ));
+        }
+        if group.size == 0 {
+            return Err(AppError::validation(
+                "groups",
+                format!("size of group {} must be >= 1", group.name),
+            ));
+        }
+        if group.task_types.is_empty() {
+            return Err(AppError::validation(
+                "groups",
+                format!("group {} has no task types", group.name),
+            ));
+        }
+        if let Some(task_type) = group
+            .task_types
+            .iter()
+            .find(|task_type| !routed.insert(task_type.as_str()))
+        {
+            return Err(AppError::validation(
+                "groups",
+                format!("task type {task_type} is assigned to more than one group"),
+            ));
+        }
+    }
+    let total = config.slots().len();
+    if total > MAX_POOL_WORKERS as usize {
+        return Err(AppError::validation(
+            "size",
+            format!("a pool of {total} workers exceeds the limit of {MAX_POOL_WORKERS}"),
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn get_worker_restart_policy(state: State<'_, AppState>) -> AppResult<WorkerRestartPolicy> {
+    WorkerManager::get_restart_policy(&state.worker_manager)
+}
+
+#[tauri::command]
+#[tracing::instrument(skip_all, err(level = "warn"))]
+pub fn set_worker_restart_policy(
+    state: State<'_, AppState>,
+    policy: WorkerRestartPolicy,
+) -> AppResult<()> {
+    validate_restart_policy(&policy)?;
+    WorkerManager::set_restart_policy(&state.worker_manager, policy)
+}
+
+fn validate_restart_policy(policy: &WorkerRestartPolicy) -> AppResult<()> {
+    if policy.restart_backoff_ms.is_empty() {
+        return Err(AppError::validation(
+            "restart_backoff_ms",
+            "at least one restart delay is required",
+        ));
+    }
+    if policy.circuit_breaker_window_secs == 0 {
+        return Err(AppError::validation(
+            "circuit_breaker_window_secs",
+            "circuit_breaker_window_secs must be >= 1",
+        ));
+    }
+    if policy.circuit_breaker_max_restarts == 0 {
+        return Err(AppError::validation(
+            "circuit_breaker_max_restarts",
+            "circuit_breaker_max_restarts must be >= 1",
+        ));
+    }
+    // Below this, a busy worker could miss a beat without being hung.
+    if policy.heartbeat_interval_ms < 100 {
+        return Err(AppError::validation(
+            "heartbeat_interval_ms",
+            "heartbeat_interval_ms must be >= 100",
+        ));
+    }
+    if policy.heartbeat_max_misses == 0 {
+        return Err(AppError::validation(
+            "heartbeat_max_misses",
+            "heartbeat_max_misses must be >= 1",
+        ));
+    }
+    Ok(())
 }
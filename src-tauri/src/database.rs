@@ -1,18 +1,49 @@
-use std::{fs, path::PathBuf, sync::Mutex};
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    thread,
+};
 
-use rusqlite::Connection;
+use chrono::Utc;
+use rusqlite::{params, Connection};
 use tauri::{AppHandle, Manager, Runtime, State};
 
-use crate::worker::manager::SharedWorkerManager;
+use crate::{
+    error::{AppError, AppResult},
+    media_store::{self, MediaStore},
+    repository::tasks_repo,
+    worker::{
+        manager::{SharedWorkerManager, WorkerManager, WorkerTimings},
+        process::ShellLauncher,
+    },
+};
+
+pub type SharedConnection = Arc<Mutex<Connection>>;
 
 pub struct AppState {
-    pub db: Mutex<Connection>,
+    pub db: SharedConnection,
+    pub media_store: MediaStore,
     pub worker_manager: SharedWorkerManager,
 }
 
 const DATABASE_FILE_NAME: &str = "dy_auto_work.sqlite3";
+const MEDIA_DIR_NAME: &str = "media";
+
+/// A single forward-only schema change. `version` must be strictly increasing
+/// across `MIGRATIONS`; each entry is applied exactly once and recorded in
+/// `schema_version`, so the SQL may freely use ALTER TABLE or backfill data.
+struct Migration {
+    version: i64,
+    description: &'static str,
+    sql: &'static str,
+}
 
-const MIGRATIONS: &[&str] = &[r#"
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "create core tables",
+        sql: r#"
 CREATE TABLE IF NOT EXISTS projects (
     id TEXT PRIMARY KEY,
     name TEXT NOT NULL,
@@ -71,7 +102,11 @@ CREATE INDEX IF NOT EXISTS idx_storyboard_scenes_script_version ON storyboard_sc
 CREATE INDEX IF NOT EXISTS idx_assets_project_id ON assets(project_id);
 CREATE INDEX IF NOT EXISTS idx_assets_scene_id ON assets(scene_id);
 "#,
-r#"
+    },
+    Migration {
+        version: 2,
+        description: "create ai_tasks",
+        sql: r#"
 CREATE TABLE IF NOT EXISTS ai_tasks (
     id TEXT PRIMARY KEY,
     project_id TEXT NOT NULL,
@@ -88,7 +123,192 @@ CREATE TABLE IF NOT EXISTS ai_tasks (
 
 CREATE INDEX IF NOT EXISTS idx_ai_tasks_project_id ON ai_tasks(project_id);
 CREATE INDEX IF NOT EXISTS idx_ai_tasks_status ON ai_tasks(status);
-"#];
+"#,
+    },
+    Migration {
+        version: 3,
+        description: "add ai_tasks.message",
+        sql: r#"
+ALTER TABLE ai_tasks ADD COLUMN message TEXT;
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_updated_at ON ai_tasks(updated_at DESC);
+"#,
+    },
+    Migration {
+        version: 4,
+        description: "link assets to the producing task",
+        sql: r#"
+ALTER TABLE assets ADD COLUMN task_id TEXT REFERENCES ai_tasks(id) ON DELETE SET NULL;
+CREATE INDEX IF NOT EXISTS idx_assets_task_id ON assets(task_id);
+"#,
+    },
+    Migration {
+        version: 5,
+        description: "create project_templates",
+        sql: r#"
+CREATE TABLE IF NOT EXISTS project_templates (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    category TEXT,
+    theme TEXT,
+    target_platform TEXT NOT NULL DEFAULT 'douyin',
+    scenes_json TEXT NOT NULL DEFAULT '[]',
+    prompt_snippets_json TEXT NOT NULL DEFAULT '[]',
+    is_builtin INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_project_templates_category ON project_templates(category);
+"#,
+    },
+    Migration {
+        version: 6,
+        description: "seed built-in project templates",
+        sql: r#"
+INSERT OR IGNORE INTO project_templates
+    (id, name, description, category, theme, target_platform, scenes_json, prompt_snippets_json, is_builtin, created_at, updated_at)
+VALUES
+(
+    'builtin-food-review', '美食探店', '适合餐厅、小吃、咖啡厅等美食类内容', '生活', '美食探店', 'douyin',
+    '[
+        {"scene_text": "开场：店铺门头与环境", "visual_prompt": "店铺门头外景，暖色调，人来人往", "duration_ms": 3000, "camera_hint": "推镜"},
+        {"scene_text": "店内氛围与装修", "visual_prompt": "店内环境全景，灯光温馨", "duration_ms": 3000, "camera_hint": "横摇"},
+        {"scene_text": "招牌菜上桌", "visual_prompt": "招牌菜特写，热气升腾", "duration_ms": 4000, "camera_hint": "特写"},
+        {"scene_text": "试吃与口感描述", "visual_prompt": "夹起食物的慢动作特写", "duration_ms": 4000, "camera_hint": "慢动作"},
+        {"scene_text": "价格与性价比", "visual_prompt": "菜单与价目表特写", "duration_ms": 3000, "camera_hint": "固定"},
+        {"scene_text": "总结与推荐指数", "visual_prompt": "满桌菜品俯拍", "duration_ms": 3000, "camera_hint": "俯拍"}
+    ]',
+    '["探店口吻，语气轻松真实，突出招牌菜的口感和性价比", "结尾给出推荐指数并引导评论区互动"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+),
+(
+    'builtin-unboxing', '产品开箱', '电子产品、美妆、服饰等开箱测评', '电商', '产品开箱', 'douyin',
+    '[
+        {"scene_text": "开场：产品包装亮相", "visual_prompt": "桌面上的产品包装盒，干净背景", "duration_ms": 3000, "camera_hint": "推镜"},
+        {"scene_text": "拆箱过程", "visual_prompt": "双手拆开包装的俯拍", "duration_ms": 4000, "camera_hint": "俯拍"},
+        {"scene_text": "配件清点", "visual_prompt": "配件整齐排列的平铺图", "duration_ms": 3000, "camera_hint": "俯拍"},
+        {"scene_text": "外观细节", "visual_prompt": "产品外观细节特写，柔光", "duration_ms": 4000, "camera_hint": "特写"},
+        {"scene_text": "核心功能上手", "visual_prompt": "手持产品实际使用画面", "duration_ms": 5000, "camera_hint": "跟拍"},
+        {"scene_text": "优缺点总结", "visual_prompt": "产品与对比项并排摆放", "duration_ms": 4000, "camera_hint": "固定"}
+    ]',
+    '["测评口吻，客观列出优缺点，不夸大宣传", "结尾说明适合人群与购买建议"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+),
+(
+    'builtin-travel-vlog', '旅行Vlog', '旅行记录、景点介绍、攻略分享', '旅行', '旅行Vlog', 'douyin',
+    '[
+        {"scene_text": "开场：目的地全景", "visual_prompt": "目的地航拍全景，晴朗天空", "duration_ms": 3000, "camera_hint": "航拍"},
+        {"scene_text": "出发与交通", "visual_prompt": "车窗外流动的风景", "duration_ms": 3000, "camera_hint": "跟拍"},
+        {"scene_text": "第一站景点", "visual_prompt": "标志性景点正面构图", "duration_ms": 4000, "camera_hint": "横摇"},
+        {"scene_text": "当地美食", "visual_prompt": "当地特色小吃特写", "duration_ms": 3000, "camera_hint": "特写"},
+        {"scene_text": "第二站景点", "visual_prompt": "人物背影望向风景", "duration_ms": 4000, "camera_hint": "拉镜"},
+        {"scene_text": "实用攻略", "visual_prompt": "地图与行程路线示意", "duration_ms": 4000, "camera_hint": "固定"},
+        {"scene_text": "结尾：日落收尾", "visual_prompt": "日落剪影，暖色调", "duration_ms": 3000, "camera_hint": "延时"}
+    ]',
+    '["第一人称旅行记录口吻，穿插实用的交通、门票与时间建议"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+),
+(
+    'builtin-talking-head', '口播解说', '知识科普、新闻解读、故事讲述', '知识', '口播解说', 'douyin',
+    '[
+        {"scene_text": "开场：抛出问题", "visual_prompt": "主讲人正面半身，简洁背景", "duration_ms": 3000, "camera_hint": "固定"},
+        {"scene_text": "背景介绍", "visual_prompt": "与主题相关的示意插图", "duration_ms": 5000, "camera_hint": "推镜"},
+        {"scene_text": "核心观点", "visual_prompt": "要点文字卡片", "duration_ms": 6000, "camera_hint": "固定"},
+        {"scene_text": "案例说明", "visual_prompt": "案例相关的资料画面", "duration_ms": 5000, "camera_hint": "横摇"},
+        {"scene_text": "总结与互动", "visual_prompt": "主讲人正面近景", "duration_ms": 3000, "camera_hint": "特写"}
+    ]',
+    '["开头三秒抛出反常识问题抓住注意力", "语言口语化，每句不超过二十字"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+),
+(
+    'builtin-photo-montage', '图文混剪', '图片轮播、照片墙、回忆录风格', '创意', '图文混剪', 'douyin',
+    '[
+        {"scene_text": "开场：标题卡", "visual_prompt": "胶片质感标题卡", "duration_ms": 2000, "camera_hint": "固定"},
+        {"scene_text": "照片一", "visual_prompt": "复古色调照片，轻微颗粒", "duration_ms": 2500, "camera_hint": "缓推"},
+        {"scene_text": "照片二", "visual_prompt": "复古色调照片，轻微颗粒", "duration_ms": 2500, "camera_hint": "缓拉"},
+        {"scene_text": "照片三", "visual_prompt": "复古色调照片，轻微颗粒", "duration_ms": 2500, "camera_hint": "横移"},
+        {"scene_text": "照片墙拼贴", "visual_prompt": "多张照片拼贴的照片墙", "duration_ms": 3000, "camera_hint": "拉镜"},
+        {"scene_text": "结尾寄语", "visual_prompt": "留白背景上的手写体文字", "duration_ms": 3000, "camera_hint": "固定"}
+    ]',
+    '["温情回忆录口吻，文案简短，配合音乐节奏切换画面"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+),
+(
+    'builtin-film-commentary', '影视解说', '电影、电视剧、综艺节目解说', '娱乐', '影视解说', 'douyin',
+    '[
+        {"scene_text": "开场：高能片段钩子", "visual_prompt": "电影感冲突画面，高对比度", "duration_ms": 3000, "camera_hint": "快切"},
+        {"scene_text": "作品信息介绍", "visual_prompt": "海报风格的作品信息卡", "duration_ms": 3000, "camera_hint": "固定"},
+        {"scene_text": "剧情铺垫", "visual_prompt": "主要人物登场画面", "duration_ms": 5000, "camera_hint": "横摇"},
+        {"scene_text": "剧情转折", "visual_prompt": "紧张氛围的暗色调画面", "duration_ms": 5000, "camera_hint": "推镜"},
+        {"scene_text": "高潮与结局", "visual_prompt": "情绪爆发的特写画面", "duration_ms": 5000, "camera_hint": "特写"},
+        {"scene_text": "个人点评", "visual_prompt": "评分卡与关键词", "duration_ms": 4000, "camera_hint": "固定"}
+    ]',
+    '["解说节奏紧凑，避免完整剧透结局的关键细节", "结尾给出评分与一句话点评"]',
+    1, strftime('%Y-%m-%dT%H:%M:%SZ', 'now'), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+);
+"#,
+    },
+    Migration {
+        version: 7,
+        description: "add task retry policies and attempt counter",
+        sql: r#"
+CREATE TABLE IF NOT EXISTS task_retry_policies (
+    task_type TEXT PRIMARY KEY,
+    max_attempts INTEGER NOT NULL CHECK(max_attempts >= 1),
+    initial_backoff_ms INTEGER NOT NULL CHECK(initial_backoff_ms >= 0),
+    max_backoff_ms INTEGER NOT NULL CHECK(max_backoff_ms >= 0),
+    retry_on_json TEXT NOT NULL DEFAULT '[]',
+    updated_at TEXT NOT NULL
+);
+
+ALTER TABLE ai_tasks ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;
+"#,
+    },
+    Migration {
+        version: 8,
+        description: "add task priority and worker settings",
+        sql: r#"
+ALTER TABLE ai_tasks ADD COLUMN priority TEXT NOT NULL DEFAULT 'normal'
+    CHECK(priority IN ('low', 'normal', 'high'));
+
+CREATE TABLE IF NOT EXISTS worker_settings (
+    key TEXT PRIMARY KEY,
+    value_json TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+"#,
+    },
+    Migration {
+        version: 9,
+        description: "add task graphs",
+        sql: r#"
+CREATE TABLE IF NOT EXISTS task_graphs (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY (project_id) REFERENCES projects(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_graphs_project_id ON task_graphs(project_id);
+
+ALTER TABLE ai_tasks ADD COLUMN graph_id TEXT REFERENCES task_graphs(id) ON DELETE CASCADE;
+ALTER TABLE ai_tasks ADD COLUMN graph_node_key TEXT;
+ALTER TABLE ai_tasks ADD COLUMN depends_on_json TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_graph_id ON ai_tasks(graph_id);
+"#,
+    },
+    Migration {
+        version: 10,
+        description: "add trace ids to ai tasks",
+        sql: r#"
+ALTER TABLE ai_tasks ADD COLUMN trace_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_ai_tasks_trace_id ON ai_tasks(trace_id);
+"#,
+    },
+];
 
 pub fn init_app_state<R: Runtime>(app: &AppHandle<R>) -> Result<AppState, String> {
     let db_path = resolve_database_path(app)?;
@@ -109,25 +329,78 @@ pub fn init_app_state<R: Runtime>(app: &AppHandle<R>) -> Result<AppState, String
 
     run_migrations(&conn)?;
 
-    let worker_manager = crate::worker::manager::WorkerManager::new();
+    let interrupted = tasks_repo::mark_interrupted(&conn)
+        .map_err(|e| format!("failed to mark interrupted tasks: {e}"))?;
+    if interrupted > 0 {
+        tracing::warn!(
+            count = interrupted,
+            "marked tasks left by the previous session as interrupted"
+        );
+    }
+
+    let media_dir = db_path
+        .parent()
+        .map(|dir| dir.join(MEDIA_DIR_NAME))
+        .ok_or_else(|| "database path has no parent directory".to_string())?;
+    let media_store = MediaStore::open(media_dir)
+        .map_err(|e| format!("failed to create media store: {e}"))?;
+
+    let db = Arc::new(Mutex::new(conn));
+    spawn_asset_adoption(Arc::clone(&db), media_store.clone());
+    let worker_manager = WorkerManager::new(
+        Arc::clone(&db),
+        media_store.clone(),
+        Arc::new(ShellLauncher::new(app.clone())),
+        WorkerTimings::default(),
+    );
 
     Ok(AppState {
-        db: Mutex::new(conn),
+        db,
+        media_store,
         worker_manager,
     })
 }
 
-pub fn with_connection<T, F>(state: &State<'_, AppState>, op: F) -> Result<T, String>
+/// Hashing every external asset can take a while, so it runs in the
+/// background instead of delaying the window.
+fn spawn_asset_adoption(db: SharedConnection, store: MediaStore) {
+    thread::spawn(
+        move || match media_store::adopt_external_assets(&db, &store) {
+            Ok(0) => {}
+            Ok(count) => tracing::info!(count, "copied external assets into media store"),
+            Err(err) => tracing::error!(%err, "failed to copy assets into media store"),
+        },
+    );
+}
+
+pub fn with_connection<T, F>(state: &State<'_, AppState>, op: F) -> AppResult<T>
 where
-    F: FnOnce(&Connection) -> Result<T, String>,
+    F: FnOnce(&Connection) -> AppResult<T>,
 {
     let guard = state
         .db
         .lock()
-        .map_err(|e| format!("database lock poisoned: {e}"))?;
+        .map_err(|e| AppError::lock_poisoned("database", e))?;
     op(&guard)
 }
 
+/// Runs `PRAGMA integrity_check`; an intact database reports a single `ok`.
+pub fn integrity_check(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA integrity_check")?;
+    let rows = stmt.query_map([], |row| row.get(0))?;
+    rows.collect()
+}
+
+/// A migrated in-memory database for tests.
+#[cfg(test)]
+pub fn open_in_memory() -> SharedConnection {
+    let conn = Connection::open_in_memory().expect("failed to open in-memory database");
+    conn.execute_batch("PRAGMA foreign_keys = ON;")
+        .expect("failed to enable foreign keys");
+    run_migrations(&conn).expect("failed to run migrations");
+    Arc::new(Mutex::new(conn))
+}
+
 fn resolve_database_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
     app.path()
         .app_data_dir()
@@ -136,9 +409,187 @@ fn resolve_database_path<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, Stri
 }
 
 fn run_migrations(conn: &Connection) -> Result<(), String> {
-    for sql in MIGRATIONS {
-        conn.execute_batch(sql)
-            .map_err(|e| format!("migration failed: {e}"))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        );",
+    )
+    .map_err(|e| format!("failed to create schema_version table: {e}"))?;
+
+    let current = current_schema_version(conn)
+        .map_err(|e| format!("failed to read schema version: {e}"))?;
+    let latest = MIGRATIONS.last().map_or(0, |m| m.version);
+    if current > latest {
+        return Err(format!(
+            "database schema version {current} is newer than this app supports ({latest}); \
+             please upgrade the app"
+        ));
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+        apply_migration(conn, migration).map_err(|e| {
+            format!(
+                "migration {} ({}) failed: {e}",
+                migration.version, migration.description
+            )
+        })?;
+        tracing::info!(
+            version = migration.version,
+            description = migration.description,
+            "applied database migration"
+        );
     }
     Ok(())
 }
+
+pub fn current_schema_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row(
+        "SELECT COALESCE(MAX(version), 0) FROM schema_version",
+        [],
+        |row| row.get(0),
+    )
+}
+
+fn apply_migration(conn: &Connection, migration: &Migration) -> rusqlite::Result<()> {
+    // Foreign keys cannot be toggled inside a transaction, and table rebuilds
+    // (the only way to change a CHECK constraint in SQLite) need them off.
+    conn.execute_batch("PRAGMA foreign_keys = OFF;")?;
+    let result = apply_migration_in_transaction(conn, migration);
+    conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+    result
+}
+
+fn apply_migration_in_transaction(conn: &Connection, migration: &Migration) -> rusqlite::Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute_batch(migration.sql)?;
+
+    let violations: i64 =
+        tx.query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))?;
+    if violations > 0 {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT_FOREIGNKEY),
+            Some(format!("{violations} foreign key violation(s) after migration")),
+        ));
+    }
+
+    tx.execute(
+        "INSERT INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+        params![migration.version, migration.description, Utc::now().to_rfc3339()],
+    )?;
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `(version, applied_at)` of every recorded migration.
+    fn schema_rows(conn: &Connection) -> Vec<(i64, String)> {
+        let mut stmt = conn
+            .prepare("SELECT version, applied_at FROM schema_version ORDER BY version")
+            .unwrap();
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap();
+        rows.collect::<rusqlite::Result<_>>().unwrap()
+    }
+
+    fn latest_version() -> i64 {
+        MIGRATIONS.last().unwrap().version
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![name],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn fresh_database_migrates_to_latest_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+
+        let versions: Vec<i64> = schema_rows(&conn).into_iter().map(|(v, _)| v).collect();
+        let expected: Vec<i64> = MIGRATIONS.iter().map(|m| m.version).collect();
+        assert_eq!(versions, expected);
+        assert_eq!(current_schema_version(&conn).unwrap(), latest_version());
+    }
+
+    #[test]
+    fn second_run_applies_nothing() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let before = schema_rows(&conn);
+
+        run_migrations(&conn).unwrap();
+
+        assert_eq!(schema_rows(&conn), before);
+    }
+
+    /// Databases created before versioned migrations have the core tables
+    /// and `ai_tasks` but no `schema_version`.
+    #[test]
+    fn baseline_database_upgrades_and_keeps_its_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        conn.execute_batch(MIGRATIONS[1].sql).unwrap();
+        conn.execute_batch(
+            "INSERT INTO projects (id, name, created_at, updated_at)
+             VALUES ('p1', 'old project', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');
+             INSERT INTO ai_tasks (id, project_id, task_type, status, created_at, updated_at)
+             VALUES ('t1', 'p1', 'script', 'completed', '2024-01-01T00:00:00Z', '2024-01-01T00:00:00Z');",
+        )
+        .unwrap();
+
+        run_migrations(&conn).unwrap();
+
+        assert_eq!(current_schema_version(&conn).unwrap(), latest_version());
+        let tasks = tasks_repo::list_by_project(&conn, "p1").unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].attempt, 1);
+        assert!(tasks[0].trace_id.is_none());
+        assert_eq!(integrity_check(&conn).unwrap(), ["ok"]);
+    }
+
+    #[test]
+    fn newer_schema_version_is_refused() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO schema_version (version, description, applied_at)
+             VALUES (?1, 'from the future', '2030-01-01T00:00:00Z')",
+            params![latest_version() + 1],
+        )
+        .unwrap();
+
+        let err = run_migrations(&conn).unwrap_err();
+        assert!(err.contains("newer than this app supports"), "{err}");
+    }
+
+    #[test]
+    fn failing_migration_rolls_back() {
+        let conn = Connection::open_in_memory().unwrap();
+        run_migrations(&conn).unwrap();
+        let broken = Migration {
+            version: latest_version() + 1,
+            description: "broken",
+            sql: "CREATE TABLE half_done (id TEXT PRIMARY KEY);
+                  ALTER TABLE no_such_table ADD COLUMN x TEXT;",
+        };
+
+        assert!(apply_migration(&conn, &broken).is_err());
+
+        assert!(!table_exists(&conn, "half_done"));
+        assert_eq!(current_schema_version(&conn).unwrap(), latest_version());
+        let foreign_keys: i64 = conn
+            .query_row("PRAGMA foreign_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(foreign_keys, 1);
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::{
+    database,
+    error::{AppError, AppResult},
+    models::DiagnosticsExportReport,
+    project_bundle::{partial_path, write_json_entry},
+    worker::manager::{WorkerDiagnostics, WorkerStatus},
+};
+
+const REPORT_ENTRY: &str = "report.json";
+const RESTARTS_ENTRY: &str = "restarts.json";
+const ENVELOPES_ENTRY: &str = "envelopes.json";
+const LOGS_PREFIX: &str = "logs/";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub os_type: String,
+    pub os_version: String,
+    pub platform: String,
+    pub family: String,
+    pub arch: String,
+    pub locale: Option<String>,
+}
+
+impl SystemInfo {
+    /// Hostname and user names are left out on purpose; bundles get shared.
+    pub fn collect() -> Self {
+        Self {
+            os_type: tauri_plugin_os::type_().to_string(),
+            os_version: tauri_plugin_os::version().to_string(),
+            platform: tauri_plugin_os::platform().to_string(),
+            family: tauri_plugin_os::family().to_string(),
+            arch: tauri_plugin_os::arch().to_string(),
+            locale: tauri_plugin_os::locale(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseDiagnostics {
+    pub schema_version: Option<i64>,
+    /// Rows reported by `PRAGMA integrity_check`, or the error that stopped it.
+    pub integrity_check: Vec<String>,
+}
+
+impl DatabaseDiagnostics {
+    pub fn is_intact(&self) -> bool {
+        self.integrity_check == ["ok"]
+    }
+}
+
+/// Header of a diagnostics bundle, written to `report.json`.
+#[derive(Debug, Serialize)]
+struct DiagnosticsReport<'a> {
+    app_version: &'static str,
+    exported_at: String,
+    system: SystemInfo,
+    database: &'a DatabaseDiagnostics,
+    worker_status: &'a WorkerStatus,
+    /// Paths of the included log files, relative to the app log directory.
+    log_files: Vec<String>,
+}
+
+/// Never fails: a database too damaged to answer is exactly what the bundle
+/// should record.
+pub fn check_database(conn: &Connection) -> DatabaseDiagnostics {
+    let schema_version = database::current_schema_version(conn).ok();
+    let integrity_check = database::integrity_check(conn)
+        .unwrap_or_else(|e| vec![format!("integrity check failed: {e}")]);
+    DatabaseDiagnostics {
+        schema_version,
+        integrity_check,
+    }
+}
+
+/// Writes the worker and database state, system info and the given log
+/// files to a zip archive at `dest`. Logs that vanish mid-export (rotated or
+/// pruned) are skipped.
+pub fn write_bundle(
+    worker: &WorkerDiagnostics,
+    database: &DatabaseDiagnostics,
+    log_dir: &Path,
+    log_files: &[PathBuf],
+    dest: &Path,
+) -> AppResult<DiagnosticsExportReport> {
+    let temp = partial_path(dest);
+    let result = write_archive(worker, database, log_dir, log_files, &temp).and_then(|logs| {
+        fs::rename(&temp, dest)
+            .map(|_| logs)
+            .map_err(|e| e.to_string())
+    });
+    let log_files = match result {
+        Ok(logs) => logs,
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            return Err(AppError::io(
+                format_args!("failed to write diagnostics {}", dest.display()),
+                err,
+            ));
+        }
+    };
+
+    Ok(DiagnosticsExportReport {
+        path: dest.to_string_lossy().into_owned(),
+        log_files,
+        restarts: worker.restarts.len(),
+        envelopes: worker.envelopes.len(),
+        database_intact: database.is_intact(),
+    })
+}
+
+/// Returns how many log files made it into the archive.
+fn write_archive(
+    worker: &WorkerDiagnostics,
+    database: &DatabaseDiagnostics,
+    log_dir: &Path,
+    log_files: &[PathBuf],
+    target: &Path,
+) -> Result<usize, String> {
+    let file = File::create(target).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut included = Vec::with_capacity(log_files.len());
+    for path in log_files {
+        let name = path
+            .strip_prefix(log_dir)
+            .unwrap_or(path)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut source = match File::open(path) {
+            Ok(source) => source,
+            Err(err) => {
+                tracing::warn!(path = %path.display(), %err, "skipping log file");
+                continue;
+            }
+        };
+        zip.start_file(format!("{LOGS_PREFIX}{name}"), deflated)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut source, &mut zip).map_err(|e| format!("{name}: {e}"))?;
+        included.push(name);
+    }
+
+    let report = DiagnosticsReport {
+        app_version: env!("CARGO_PKG_VERSION"),
+        exported_at: Utc::now().to_rfc3339(),
+        system: SystemInfo::collect(),
+        database,
+        worker_status: &worker.status,
+        log_files: included,
+    };
+    write_json_entry(&mut zip, REPORT_ENTRY, &report, deflated)?;
+    write_json_entry(&mut zip, RESTARTS_ENTRY, &worker.restarts, deflated)?;
+    write_json_entry(&mut zip, ENVELOPES_ENTRY, &worker.envelopes, deflated)?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(report.log_files.len())
+}
@@ -0,0 +1,96 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable error category. The serialized names are part of the
+/// IPC contract with the frontend and must not be renamed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    NotFound,
+    Validation,
+    Constraint,
+    Database,
+    LockPoisoned,
+    UnsupportedFormat,
+    WorkerNotRunning,
+    WorkerUnavailable,
+    Worker,
+    Io,
+    Internal,
+}
+
+/// Error returned by every command. `field` names the offending input field
+/// for validation errors so the UI can attach the message to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field: None,
+        }
+    }
+
+    pub fn not_found(what: &str, id: &str) -> Self {
+        Self::new(ErrorCode::NotFound, format!("{what} {id} not found"))
+    }
+
+    pub fn validation(field: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: Some(field.to_string()),
+            ..Self::new(ErrorCode::Validation, message)
+        }
+    }
+
+    pub fn lock_poisoned(what: &str, err: impl fmt::Display) -> Self {
+        Self::new(
+            ErrorCode::LockPoisoned,
+            format!("{what} lock poisoned: {err}"),
+        )
+    }
+
+    pub fn worker(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Worker, message)
+    }
+
+    pub fn io(context: impl fmt::Display, err: impl fmt::Display) -> Self {
+        Self::new(ErrorCode::Io, format!("{context}: {err}"))
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(err: rusqlite::Error) -> Self {
+        let code = match &err {
+            rusqlite::Error::QueryReturnedNoRows => ErrorCode::NotFound,
+            rusqlite::Error::SqliteFailure(failure, _)
+                if failure.code == rusqlite::ErrorCode::ConstraintViolation =>
+            {
+                ErrorCode::Constraint
+            }
+            _ => ErrorCode::Database,
+        };
+        Self::new(code, err.to_string())
+    }
+}
@@ -1,8 +1,13 @@
+use std::fmt;
+
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+/// Newest protocol version the host speaks.
 pub const IPC_VERSION: &str = "1.0";
+/// Oldest protocol version the host still speaks.
+pub const MIN_IPC_VERSION: &str = "1.0";
 
 pub const EVENT_WORKER_HELLO: &str = "worker.hello";
 pub const EVENT_WORKER_WELCOME: &str = "worker.welcome";
@@ -13,6 +18,65 @@ pub const EVENT_TASK_STARTED: &str = "task.started";
 pub const EVENT_TASK_PROGRESS: &str = "task.progress";
 pub const EVENT_TASK_COMPLETED: &str = "task.completed";
 pub const EVENT_TASK_FAILED: &str = "task.failed";
+pub const EVENT_TASK_CANCELLED: &str = "task.cancelled";
+
+/// A `major.minor` protocol version. Minor versions only add optional fields
+/// and events, so two peers on the same major can talk at the lower minor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpcVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl IpcVersion {
+    pub fn parse(version: &str) -> Option<Self> {
+        let (major, minor) = version.trim().split_once('.')?;
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+        })
+    }
+
+    pub fn newest() -> Self {
+        Self::parse(IPC_VERSION).expect("IPC_VERSION is a valid version")
+    }
+
+    pub fn oldest() -> Self {
+        Self::parse(MIN_IPC_VERSION).expect("MIN_IPC_VERSION is a valid version")
+    }
+}
+
+impl fmt::Display for IpcVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// Picks the highest version both sides speak out of the versions a worker
+/// declared. A worker on a newer minor of the host's major is met at the
+/// host's newest version. `None` means the two cannot talk.
+pub fn negotiate_ipc_version(worker_versions: &[String]) -> Option<IpcVersion> {
+    negotiate(worker_versions, IpcVersion::oldest(), IpcVersion::newest())
+}
+
+fn negotiate(
+    worker_versions: &[String],
+    oldest: IpcVersion,
+    newest: IpcVersion,
+) -> Option<IpcVersion> {
+    worker_versions
+        .iter()
+        .filter_map(|version| IpcVersion::parse(version))
+        .map(|version| {
+            if version.major == newest.major {
+                version.min(newest)
+            } else {
+                version
+            }
+        })
+        .filter(|version| (oldest..=newest).contains(version))
+        .max()
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -31,6 +95,9 @@ pub struct Envelope {
     pub msg_id: String,
     pub trace_id: String,
     pub payload: Value,
+    /// `msg_id` of the command an Ack or Error answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
 }
 
 impl Envelope {
@@ -42,7 +109,23 @@ impl Envelope {
             msg_id: Uuid::new_v4().to_string(),
             trace_id: Uuid::new_v4().to_string(),
             payload,
+            reply_to: None,
+        }
+    }
+
+    /// Carries an existing trace instead of the fresh one; `None` keeps it.
+    pub fn with_trace_id(mut self, trace_id: Option<&str>) -> Self {
+        if let Some(trace_id) = trace_id {
+            self.trace_id = trace_id.to_string();
         }
+        self
+    }
+
+    /// Marks this envelope as the answer to `message`, sharing its trace.
+    pub fn in_reply_to(mut self, message: &Envelope) -> Self {
+        self.reply_to = Some(message.msg_id.clone());
+        self.trace_id = message.trace_id.clone();
+        self
     }
 
     pub fn to_ndjson_line(&self) -> Result<String, serde_json::Error> {
@@ -55,6 +138,44 @@ impl Envelope {
     }
 }
 
+/// Payload of `worker.hello`. Every field is optional so older workers that
+/// send an empty hello are still accepted; an empty list means "not declared"
+/// and, for `ipc_versions`, falls back to the hello envelope's own `v`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkerCapabilities {
+    #[serde(default)]
+    pub ipc_versions: Vec<String>,
+    #[serde(default)]
+    pub task_types: Vec<TaskTypeCapability>,
+    #[serde(default)]
+    pub max_concurrency: Option<u32>,
+    #[serde(default)]
+    pub build: Option<WorkerBuildInfo>,
+}
+
+impl WorkerCapabilities {
+    pub fn supports_task_type(&self, task_type: &str) -> bool {
+        self.task_types.is_empty() || self.task_types.iter().any(|t| t.task_type == task_type)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskTypeCapability {
+    pub task_type: String,
+    /// JSON Schema of the task's `config`, if the worker publishes one.
+    #[serde(default)]
+    pub config_schema: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerBuildInfo {
+    pub version: String,
+    #[serde(default)]
+    pub commit: Option<String>,
+    #[serde(default)]
+    pub built_at: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskPayload {
     pub task_id: String,
@@ -76,6 +197,22 @@ pub struct TaskResultPayload {
     pub status: String,
     pub output: Option<Value>,
     pub error: Option<String>,
+    /// Failure class used by the retry policy: `timeout`, `rate_limited` or
+    /// `network`. Anything else, including no code, counts as `failed`.
+    #[serde(default)]
+    pub error_code: Option<String>,
+}
+
+/// Files produced by a task, listed under `output.assets` of a
+/// `task.completed` payload. Paths must be absolute and readable by the host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputAssetRef {
+    #[serde(rename = "type")]
+    pub asset_type: String,
+    pub path: String,
+    pub scene_id: Option<String>,
+    pub provider: Option<String>,
+    pub meta: Option<Value>,
 }
 
 pub fn deserialize_ndjson(input: &str) -> Result<Vec<Envelope>, serde_json::Error> {
@@ -86,3 +223,49 @@ pub fn deserialize_ndjson(input: &str) -> Result<Vec<Envelope>, serde_json::Erro
         .map(serde_json::from_str)
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(list: &[&str]) -> Vec<String> {
+        list.iter().map(|version| version.to_string()).collect()
+    }
+
+    fn v(major: u32, minor: u32) -> IpcVersion {
+        IpcVersion { major, minor }
+    }
+
+    #[test]
+    fn newer_minor_settles_on_host_version() {
+        assert_eq!(negotiate_ipc_version(&versions(&["1.3"])), Some(v(1, 0)));
+        assert_eq!(
+            negotiate(&versions(&["1.3"]), v(1, 0), v(1, 2)),
+            Some(v(1, 2))
+        );
+    }
+
+    #[test]
+    fn highest_common_version_wins() {
+        let offered = versions(&["0.9", "1.1", "1.0", "2.0"]);
+        assert_eq!(negotiate(&offered, v(1, 0), v(1, 4)), Some(v(1, 1)));
+        assert_eq!(negotiate_ipc_version(&offered), Some(v(1, 0)));
+    }
+
+    #[test]
+    fn unparsable_versions_are_ignored() {
+        for garbage in ["", "1", "one.zero", "1.x", "-1.0", "1.0.0"] {
+            let offered = versions(&[garbage]);
+            assert_eq!(negotiate_ipc_version(&offered), None, "{garbage}");
+        }
+        let offered = versions(&["banana", "1.0"]);
+        assert_eq!(negotiate_ipc_version(&offered), Some(v(1, 0)));
+    }
+
+    #[test]
+    fn versions_outside_the_host_range_are_rejected() {
+        assert_eq!(negotiate_ipc_version(&versions(&["0.9"])), None);
+        assert_eq!(negotiate(&versions(&["1.1"]), v(1, 2), v(1, 4)), None);
+        assert_eq!(negotiate_ipc_version(&versions(&["2.0"])), None);
+    }
+}
@@ -1,7 +1,12 @@
 mod commands;
 mod database;
+mod diagnostics;
+mod error;
 mod ipc;
+mod logging;
+mod media_store;
 mod models;
+mod project_bundle;
 mod repository;
 mod worker;
 
@@ -19,6 +24,9 @@ pub fn run() {
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_shell::init())
         .setup(|app| {
+            if let Err(err) = logging::init(app.handle()) {
+                eprintln!("failed to set up logging: {err}");
+            }
             let state = database::init_app_state(app.handle()).map_err(|e| {
                 std::io::Error::other(e)
             })?;
@@ -32,6 +40,9 @@ pub fn run() {
             commands::project_commands::get_project,
             commands::project_commands::update_project,
             commands::project_commands::delete_project,
+            commands::project_commands::duplicate_project,
+            commands::project_commands::export_project,
+            commands::project_commands::import_project,
             commands::script_commands::create_script_version,
             commands::script_commands::get_latest_script,
             commands::script_commands::list_script_versions,
@@ -40,12 +51,46 @@ pub fn run() {
             commands::scene_commands::update_scene,
             commands::scene_commands::delete_scene,
             commands::scene_commands::reorder_scenes,
+            commands::template_commands::save_project_as_template,
+            commands::template_commands::list_project_templates,
+            commands::template_commands::get_project_template,
+            commands::template_commands::create_project_from_template,
+            commands::template_commands::delete_project_template,
+            commands::asset_commands::create_asset,
+            commands::asset_commands::list_assets,
+            commands::asset_commands::list_scene_assets,
+            commands::asset_commands::get_asset,
+            commands::asset_commands::update_asset,
+            commands::asset_commands::delete_asset,
+            commands::asset_commands::import_asset,
+            commands::asset_commands::resolve_asset_path,
+            commands::asset_commands::collect_asset_garbage,
             commands::worker_commands::get_worker_status,
             commands::worker_commands::submit_task,
             commands::worker_commands::cancel_task,
+            commands::worker_commands::reprioritize_task,
+            commands::worker_commands::submit_task_graph,
+            commands::worker_commands::cancel_task_graph,
+            commands::worker_commands::get_task_graph,
+            commands::worker_commands::list_task_graphs,
             commands::worker_commands::list_tasks,
             commands::worker_commands::start_worker,
             commands::worker_commands::stop_worker,
+            commands::worker_commands::list_retry_policies,
+            commands::worker_commands::set_retry_policy,
+            commands::worker_commands::delete_retry_policy,
+            commands::worker_commands::get_concurrency_limits,
+            commands::worker_commands::set_concurrency_limits,
+            commands::worker_commands::get_task_timeouts,
+            commands::worker_commands::set_task_timeouts,
+            commands::worker_commands::get_worker_launch_config,
+            commands::worker_commands::set_worker_launch_config,
+            commands::worker_commands::get_worker_pool_config,
+            commands::worker_commands::set_worker_pool_config,
+            commands::worker_commands::get_worker_restart_policy,
+            commands::worker_commands::set_worker_restart_policy,
+            commands::worker_commands::reset_worker_circuit,
+            commands::diagnostics_commands::export_diagnostics,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
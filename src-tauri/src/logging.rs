@@ -0,0 +1,159 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use chrono::Local;
+use tauri::{AppHandle, Manager, Runtime};
+use tracing_subscriber::{filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt};
+
+const LOG_FILE_NAME: &str = "dy-auto-work.log";
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_ROTATED_LOG_FILES: usize = 5;
+const WORKER_LOG_DIR_NAME: &str = "worker";
+const MAX_WORKER_SESSION_LOGS: usize = 20;
+const DIAGNOSTIC_WORKER_SESSION_LOGS: usize = 5;
+
+/// Sends `tracing` events to stderr and to a size-rotated file in the app log
+/// directory. Must run before anything else logs.
+pub fn init<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let log_dir = resolve_log_dir(app)?;
+    let file = RotatingFile::open(log_dir.join(LOG_FILE_NAME))
+        .map_err(|e| format!("failed to open log file in {}: {e}", log_dir.display()))?;
+
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(fmt::layer().with_writer(io::stderr))
+        .with(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        .try_init()
+        .map_err(|e| format!("failed to install logger: {e}"))
+}
+
+/// Opens the file that captures one worker session's stderr, removing the
+/// oldest session files beyond `MAX_WORKER_SESSION_LOGS`.
+pub fn open_worker_session_log<R: Runtime>(
+    app: &AppHandle<R>,
+    session_id: u64,
+) -> Result<File, String> {
+    let dir = resolve_log_dir(app)?.join(WORKER_LOG_DIR_NAME);
+    fs::create_dir_all(&dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+    prune_worker_session_logs(&dir);
+
+    // Timestamp and padded session id keep the files in chronological order by name.
+    let name = format!(
+        "dy-worker-{}-{session_id:04}.log",
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    File::create(dir.join(&name)).map_err(|e| format!("failed to create {name}: {e}"))
+}
+
+/// Returns the app log directory together with the files a diagnostics
+/// bundle should include: the app log, its rotations and the most recent
+/// worker session logs.
+pub fn recent_log_files<R: Runtime>(app: &AppHandle<R>) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let dir = resolve_log_dir(app)?;
+    let mut files: Vec<PathBuf> = (0..=MAX_ROTATED_LOG_FILES)
+        .map(|index| match index {
+            0 => dir.join(LOG_FILE_NAME),
+            index => dir.join(format!("{LOG_FILE_NAME}.{index}")),
+        })
+        .filter(|path| path.is_file())
+        .collect();
+
+    let mut sessions = worker_session_logs(&dir.join(WORKER_LOG_DIR_NAME));
+    let skip = sessions
+        .len()
+        .saturating_sub(DIAGNOSTIC_WORKER_SESSION_LOGS);
+    files.extend(sessions.drain(skip..));
+    Ok((dir, files))
+}
+
+fn resolve_log_dir<R: Runtime>(app: &AppHandle<R>) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("failed to resolve app_log_dir: {e}"))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("failed to create app log directory: {e}"))?;
+    Ok(dir)
+}
+
+/// Worker session logs in `dir`, oldest first.
+fn worker_session_logs(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn prune_worker_session_logs(dir: &Path) {
+    let files = worker_session_logs(dir);
+    if files.len() < MAX_WORKER_SESSION_LOGS {
+        return;
+    }
+    for path in &files[..=files.len() - MAX_WORKER_SESSION_LOGS] {
+        if let Err(err) = fs::remove_file(path) {
+            tracing::warn!(path = %path.display(), %err, "failed to remove old worker log");
+        }
+    }
+}
+
+/// Append-only log file that moves itself to `<name>.1` once it grows past
+/// `MAX_LOG_FILE_BYTES`, shifting older files up to `MAX_ROTATED_LOG_FILES`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    len: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let len = file.metadata()?.len();
+        Ok(Self { path, file, len })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..MAX_ROTATED_LOG_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.len > 0 && self.len + buf.len() as u64 > MAX_LOG_FILE_BYTES {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -0,0 +1,648 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::OsStr,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use rusqlite::Connection;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    database::SharedConnection,
+    error::{AppError, AppResult},
+    models::{Asset, AssetGcInput, AssetGcReport, StoredFileEntry, UpdateAssetInput},
+    repository::assets_repo,
+};
+
+/// Files younger than this are never treated as garbage: an import copies the
+/// file into the store before its `assets` row is inserted.
+const GC_GRACE_PERIOD: Duration = Duration::from_secs(10 * 60);
+
+/// Content-addressed file store living next to the database. Files are
+/// keyed by their SHA-256 as `<first two hex chars>/<sha256>.<ext>`, so
+/// importing identical content twice yields the same store-relative path.
+#[derive(Debug, Clone)]
+pub struct MediaStore {
+    root: PathBuf,
+}
+
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    pub relative_path: String,
+    pub checksum: String,
+}
+
+impl MediaStore {
+    pub fn open(root: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&root)?;
+        Ok(Self { root })
+    }
+
+    /// Copies `source` into the store unless a file with the same content is
+    /// already present.
+    pub fn import_file(&self, source: &Path) -> io::Result<StoredFile> {
+        let checksum = sha256_file(source)?;
+        let relative_path = relative_path_for(&checksum, source.extension());
+        let target = self.root.join(&relative_path);
+
+        if !target.is_file() {
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Copy under a unique name first so a crash never leaves a
+            // truncated file at the content-addressed path.
+            let temp = target.with_file_name(format!(".{}.tmp", Uuid::new_v4()));
+            if let Err(err) = fs::copy(source, &temp).and_then(|_| fs::rename(&temp, &target)) {
+                let _ = fs::remove_file(&temp);
+                return Err(err);
+            }
+        }
+
+        Ok(StoredFile {
+            relative_path,
+            checksum,
+        })
+    }
+
+    /// Streams `reader` into the store for sources that are not plain files,
+    /// such as entries of a project bundle. The content is hashed while it is
+    /// written to a temporary file, which is then moved to its final path.
+    pub fn import_reader(
+        &self,
+        reader: &mut impl Read,
+        extension: Option<&OsStr>,
+    ) -> io::Result<StoredFile> {
+        let temp = self.root.join(format!(".{}.tmp", Uuid::new_v4()));
+        let result = write_hashed(reader, &temp).and_then(|checksum| {
+            let relative_path = relative_path_for(&checksum, extension);
+            let target = self.root.join(&relative_path);
+            if target.is_file() {
+                fs::remove_file(&temp)?;
+            } else {
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(&temp, &target)?;
+            }
+            Ok(StoredFile {
+                relative_path,
+                checksum,
+            })
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        result
+    }
+
+    /// Resolves a store-relative path to an absolute one, rejecting anything
+    /// that could escape the store root.
+    pub fn resolve(&self, relative_path: &str) -> Option<PathBuf> {
+        let path = Path::new(relative_path);
+        let is_contained = !relative_path.is_empty()
+            && path
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        is_contained.then(|| self.root.join(path))
+    }
+
+    pub fn contains(&self, relative_path: &str) -> bool {
+        self.resolve(relative_path)
+            .is_some_and(|path| path.is_file())
+    }
+
+    fn list_files(&self) -> io::Result<Vec<(StoredFileEntry, SystemTime)>> {
+        let mut files = Vec::new();
+        let mut pending = vec![self.root.clone()];
+        while let Some(dir) = pending.pop() {
+            for entry in fs::read_dir(&dir)? {
+                let entry = entry?;
+                let metadata = entry.metadata()?;
+                if metadata.is_dir() {
+                    pending.push(entry.path());
+                    continue;
+                }
+                let Ok(relative) = entry.path().strip_prefix(&self.root).map(Path::to_path_buf)
+                else {
+                    continue;
+                };
+                let relative_path = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((
+                    StoredFileEntry {
+                        relative_path,
+                        size_bytes: metadata.len(),
+                    },
+                    metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                ));
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Copies the files of assets whose `local_path` still points outside the
+/// store (rows written before the store existed) into it and points the
+/// assets at the copies. The original files are left where they are, and
+/// missing ones are skipped. Files are hashed without holding the database
+/// lock; rows changed in the meantime are left alone. Returns the number of
+/// assets adopted.
+pub fn adopt_external_assets(db: &SharedConnection, store: &MediaStore) -> AppResult<usize> {
+    let assets = {
+        let conn = db
+            .lock()
+            .map_err(|e| AppError::lock_poisoned("database", e))?;
+        assets_repo::list_all(&conn)?
+    };
+    let imported = import_external_files(store, &assets);
+    if imported.is_empty() {
+        return Ok(0);
+    }
+
+    let conn = db
+        .lock()
+        .map_err(|e| AppError::lock_poisoned("database", e))?;
+    let tx = conn.unchecked_transaction()?;
+    let mut adopted = 0;
+    for asset in &assets {
+        let Some(stored) = asset.local_path.as_ref().and_then(|p| imported.get(p)) else {
+            continue;
+        };
+        let current = assets_repo::get_by_id(&tx, &asset.id)?;
+        if current.and_then(|current| current.local_path) != asset.local_path {
+            continue;
+        }
+        adopt_asset(&tx, &asset.id, stored)?;
+        adopted += 1;
+    }
+    tx.commit()?;
+    Ok(adopted)
+}
+
+/// Copies the files of `assets` whose `local_path` still points outside the
+/// store into it, keyed by that path. Missing files are skipped and failed
+/// copies only logged. No database rows are touched, so this can run without
+/// holding the connection lock.
+pub fn import_external_files(store: &MediaStore, assets: &[Asset]) -> HashMap<String, StoredFile> {
+    let mut imported = HashMap::new();
+    for local_path in assets.iter().filter_map(|a| a.local_path.as_deref()) {
+        let source = Path::new(local_path);
+        if !source.is_absolute() || !source.is_file() || imported.contains_key(local_path) {
+            continue;
+        }
+        match store.import_file(source) {
+            Ok(stored) => {
+                imported.insert(local_path.to_string(), stored);
+            }
+            Err(err) => {
+                tracing::warn!(path = %local_path, %err, "failed to adopt asset into media store");
+            }
+        }
+    }
+    imported
+}
+
+/// Points an asset at its file in the store.
+pub fn adopt_asset(conn: &Connection, asset_id: &str, stored: &StoredFile) -> rusqlite::Result<()> {
+    assets_repo::update(
+        conn,
+        asset_id,
+        UpdateAssetInput {
+            local_path: Some(stored.relative_path.clone()),
+            checksum: Some(stored.checksum.clone()),
+            ..Default::default()
+        },
+    )?;
+    Ok(())
+}
+
+/// Finds store files no asset references, assets whose file is gone and
+/// assets detached from every scene, deleting them unless `dry_run` is set.
+/// Scene-less assets are only deleted when `purge_sceneless` is set, since
+/// project-level media legitimately has no scene.
+pub fn collect_garbage(
+    conn: &Connection,
+    store: &MediaStore,
+    input: &AssetGcInput,
+) -> AppResult<AssetGcReport> {
+    let assets = assets_repo::list_all(conn)?;
+    let files = store
+        .list_files()
+        .map_err(|e| AppError::io("failed to scan media store", e))?;
+    let file_sizes: HashMap<&str, u64> = files
+        .iter()
+        .map(|(entry, _)| (entry.relative_path.as_str(), entry.size_bytes))
+        .collect();
+
+    let (missing_file_assets, present_assets): (Vec<Asset>, Vec<Asset>) =
+        assets.into_iter().partition(|asset| {
+            asset
+                .local_path
+                .as_deref()
+                .is_some_and(|path| !asset_file_exists(store, path))
+        });
+    let sceneless_assets: Vec<Asset> = present_assets
+        .iter()
+        .filter(|asset| asset.scene_id.is_none())
+        .cloned()
+        .collect();
+
+    let doomed_ids: HashSet<String> = missing_file_assets
+        .iter()
+        .chain(sceneless_assets.iter().filter(|_| input.purge_sceneless))
+        .map(|asset| asset.id.clone())
+        .collect();
+    let still_referenced: HashSet<&str> = present_assets
+        .iter()
+        .filter(|asset| !doomed_ids.contains(asset.id.as_str()))
+        .filter_map(|asset| asset.local_path.as_deref())
+        .collect();
+
+    let cutoff = SystemTime::now()
+        .checked_sub(GC_GRACE_PERIOD)
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+    let unreferenced_files: Vec<StoredFileEntry> = files
+        .iter()
+        .filter(|(entry, modified)| {
+            !still_referenced.contains(entry.relative_path.as_str()) && *modified < cutoff
+        })
+        .map(|(entry, _)| entry.clone())
+        .collect();
+    // Files of purged scene-less assets are unreferenced but may be younger
+    // than the grace period; they are still freed because the row goes away.
+    let freed_paths: HashSet<String> = present_assets
+        .iter()
+        .filter(|asset| doomed_ids.contains(asset.id.as_str()))
+        .filter_map(|asset| asset.local_path.as_deref())
+        .filter(|path| !still_referenced.contains(path))
+        .chain(
+            unreferenced_files
+                .iter()
+                .map(|entry| entry.relative_path.as_str()),
+        )
+        .map(str::to_owned)
+        .collect();
+    let reclaimable_bytes = freed_paths
+        .iter()
+        .filter_map(|path| file_sizes.get(path.as_str()))
+        .sum();
+
+    let mut report = AssetGcReport {
+        dry_run: input.dry_run,
+        unreferenced_files,
+        missing_file_assets,
+        sceneless_assets,
+        reclaimable_bytes,
+        deleted_files: 0,
+        deleted_assets: 0,
+    };
+    if input.dry_run {
+        return Ok(report);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    for id in &doomed_ids {
+        if assets_repo::delete(&tx, id)? {
+            report.deleted_assets += 1;
+        }
+    }
+    tx.commit()?;
+
+    for path in freed_paths {
+        let Some(absolute) = store.resolve(&path) else {
+            continue;
+        };
+        match fs::remove_file(&absolute) {
+            Ok(()) => report.deleted_files += 1,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => tracing::warn!(%path, %err, "failed to delete media file"),
+        }
+    }
+    Ok(report)
+}
+
+/// Store-relative paths are the norm; absolute paths only survive for rows
+/// whose file was already missing when the store adopted external assets.
+fn asset_file_exists(store: &MediaStore, local_path: &str) -> bool {
+    let path = Path::new(local_path);
+    if path.is_absolute() {
+        return path.is_file();
+    }
+    store.contains(local_path)
+}
+
+fn relative_path_for(checksum: &str, extension: Option<&OsStr>) -> String {
+    let extension = extension
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase)
+        .filter(|ext| {
+            !ext.is_empty() && ext.len() <= 10 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+    match extension {
+        Some(ext) => format!("{}/{checksum}.{ext}", &checksum[..2]),
+        None => format!("{}/{checksum}", &checksum[..2]),
+    }
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn write_hashed(reader: &mut impl Read, target: &Path) -> io::Result<String> {
+    let mut file = File::create(target)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        file.write_all(&buffer[..read])?;
+    }
+    file.sync_all()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        database,
+        models::{CreateAssetInput, CreateProjectInput, CreateSceneInput},
+        repository::{projects_repo, scenes_repo},
+    };
+
+    /// A store under a scratch directory that also holds source files.
+    struct Fixture {
+        dir: PathBuf,
+        store: MediaStore,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn fixture() -> Fixture {
+        let dir = env::temp_dir().join(format!("dy-media-test-{}", Uuid::new_v4()));
+        let store = MediaStore::open(dir.join("media")).unwrap();
+        Fixture { dir, store }
+    }
+
+    impl Fixture {
+        fn source(&self, name: &str, content: &[u8]) -> PathBuf {
+            let path = self.dir.join(name);
+            fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn stored_files(&self) -> Vec<String> {
+            let mut paths: Vec<String> = self
+                .store
+                .list_files()
+                .unwrap()
+                .into_iter()
+                .map(|(entry, _)| entry.relative_path)
+                .collect();
+            paths.sort();
+            paths
+        }
+
+        fn store_file(&self, name: &str, content: &[u8]) -> StoredFile {
+            self.store.import_file(&self.source(name, content)).unwrap()
+        }
+
+        /// Backdates a stored file past the garbage collection grace period.
+        fn age(&self, stored: &StoredFile) {
+            let path = self.store.resolve(&stored.relative_path).unwrap();
+            let file = File::options().write(true).open(path).unwrap();
+            let modified = SystemTime::now() - GC_GRACE_PERIOD - Duration::from_secs(60);
+            file.set_modified(modified).unwrap();
+        }
+    }
+
+    /// A project with a single scene, for garbage collection tests.
+    struct Library {
+        db: SharedConnection,
+        project_id: String,
+        scene_id: String,
+    }
+
+    fn library() -> Library {
+        let db = database::open_in_memory();
+        let (project_id, scene_id) = {
+            let conn = db.lock().unwrap();
+            let input = CreateProjectInput {
+                name: "gc test".into(),
+                theme: None,
+                target_platform: None,
+            };
+            let project = projects_repo::create(&conn, input).unwrap();
+            let input = CreateSceneInput {
+                project_id: project.id.clone(),
+                script_version_id: None,
+                scene_text: "scene".into(),
+                visual_prompt: None,
+                duration_ms: None,
+                camera_hint: None,
+            };
+            let scene = scenes_repo::create(&conn, input).unwrap();
+            (project.id, scene.id)
+        };
+        Library {
+            db,
+            project_id,
+            scene_id,
+        }
+    }
+
+    impl Library {
+        fn add_asset(&self, local_path: &str, on_scene: bool) -> String {
+            let conn = self.db.lock().unwrap();
+            let input = CreateAssetInput {
+                project_id: self.project_id.clone(),
+                scene_id: on_scene.then(|| self.scene_id.clone()),
+                task_id: None,
+                asset_type: "image".into(),
+                provider: None,
+                local_path: Some(local_path.into()),
+                meta_json: None,
+                checksum: None,
+                status: None,
+            };
+            assets_repo::create(&conn, input).unwrap().id
+        }
+
+        fn gc(&self, f: &Fixture, dry_run: bool, purge_sceneless: bool) -> AssetGcReport {
+            let conn = self.db.lock().unwrap();
+            let input = AssetGcInput {
+                dry_run,
+                purge_sceneless,
+            };
+            collect_garbage(&conn, &f.store, &input).unwrap()
+        }
+
+        fn asset_ids(&self) -> Vec<String> {
+            let conn = self.db.lock().unwrap();
+            let mut ids: Vec<String> = assets_repo::list_all(&conn)
+                .unwrap()
+                .into_iter()
+                .map(|asset| asset.id)
+                .collect();
+            ids.sort();
+            ids
+        }
+    }
+
+    #[test]
+    fn identical_content_is_stored_once() {
+        let f = fixture();
+        let first = f.store.import_file(&f.source("a.PNG", b"same")).unwrap();
+        let second = f.store.import_file(&f.source("b.png", b"same")).unwrap();
+        let other = f
+            .store
+            .import_file(&f.source("c.png", b"different"))
+            .unwrap();
+
+        assert_eq!(first.relative_path, second.relative_path);
+        assert_eq!(first.checksum, second.checksum);
+        assert_ne!(first.relative_path, other.relative_path);
+        let expected = format!("{}/{}.png", &first.checksum[..2], first.checksum);
+        assert_eq!(first.relative_path, expected);
+        let mut expected = vec![first.relative_path, other.relative_path];
+        expected.sort();
+        assert_eq!(f.stored_files(), expected);
+    }
+
+    #[test]
+    fn resolve_stays_inside_the_store() {
+        let f = fixture();
+        assert!(f.store.resolve("ab/abcdef.png").is_some());
+        for escaping in ["", "../secret.png", "ab/../../secret.png", "./ab.png"] {
+            assert!(f.store.resolve(escaping).is_none(), "{escaping}");
+        }
+        let absolute = f.dir.join("media/ab/abcdef.png");
+        assert!(f.store.resolve(&absolute.to_string_lossy()).is_none());
+    }
+
+    #[test]
+    fn external_assets_are_copied_and_relinked() {
+        let f = fixture();
+        let db = database::open_in_memory();
+        let external = f.source("voice.mp3", b"audio");
+        let asset_id = {
+            let conn = db.lock().unwrap();
+            let input = CreateProjectInput {
+                name: "media test".into(),
+                theme: None,
+                target_platform: None,
+            };
+            let project = projects_repo::create(&conn, input).unwrap();
+            let input = CreateAssetInput {
+                project_id: project.id,
+                scene_id: None,
+                task_id: None,
+                asset_type: "audio".into(),
+                provider: None,
+                local_path: Some(external.to_string_lossy().into_owned()),
+                meta_json: None,
+                checksum: None,
+                status: None,
+            };
+            assets_repo::create(&conn, input).unwrap().id
+        };
+
+        assert_eq!(adopt_external_assets(&db, &f.store).unwrap(), 1);
+
+        let asset = {
+            let conn = db.lock().unwrap();
+            assets_repo::get_by_id(&conn, &asset_id).unwrap().unwrap()
+        };
+        let local_path = asset.local_path.unwrap();
+        assert!(f.store.contains(&local_path));
+        assert_eq!(
+            asset.checksum.as_deref(),
+            local_path.split(['/', '.']).nth(1)
+        );
+        assert!(external.is_file(), "the original file is left in place");
+        assert_eq!(adopt_external_assets(&db, &f.store).unwrap(), 0);
+    }
+
+    #[test]
+    fn dry_run_reports_what_a_purge_deletes() {
+        let f = fixture();
+        let lib = library();
+        let old_orphan = f.store_file("old.png", b"orphan!");
+        f.age(&old_orphan);
+        let young_orphan = f.store_file("young.png", b"just imported");
+        let referenced = f.store_file("kept.png", b"in use");
+        f.age(&referenced);
+        lib.add_asset(&referenced.relative_path, true);
+        let missing = lib.add_asset("ab/missing.png", true);
+        let files_before = f.stored_files();
+        let assets_before = lib.asset_ids();
+
+        let report = lib.gc(&f, true, false);
+        let unreferenced: Vec<&str> = report
+            .unreferenced_files
+            .iter()
+            .map(|entry| entry.relative_path.as_str())
+            .collect();
+        assert_eq!(unreferenced, [old_orphan.relative_path.as_str()]);
+        assert_eq!(report.missing_file_assets.len(), 1);
+        assert_eq!(report.missing_file_assets[0].id, missing);
+        assert_eq!(report.reclaimable_bytes, 7);
+        assert_eq!((report.deleted_files, report.deleted_assets), (0, 0));
+        assert_eq!(f.stored_files(), files_before);
+        assert_eq!(lib.asset_ids(), assets_before);
+
+        let report = lib.gc(&f, false, false);
+        assert_eq!((report.deleted_files, report.deleted_assets), (1, 1));
+        assert!(!f.store.contains(&old_orphan.relative_path));
+        assert!(f.store.contains(&young_orphan.relative_path));
+        assert!(f.store.contains(&referenced.relative_path));
+        assert!(!lib.asset_ids().contains(&missing));
+    }
+
+    #[test]
+    fn sceneless_assets_are_purged_only_on_request() {
+        let f = fixture();
+        let lib = library();
+        let shared = f.store_file("shared.png", b"shared");
+        lib.add_asset(&shared.relative_path, false);
+        lib.add_asset(&shared.relative_path, false);
+        let mixed = f.store_file("mixed.png", b"still used by a scene");
+        lib.add_asset(&mixed.relative_path, false);
+        let on_scene = lib.add_asset(&mixed.relative_path, true);
+
+        let report = lib.gc(&f, true, false);
+        assert_eq!(report.sceneless_assets.len(), 3);
+        assert_eq!(report.reclaimable_bytes, 0);
+        let report = lib.gc(&f, false, false);
+        assert_eq!((report.deleted_files, report.deleted_assets), (0, 0));
+
+        // The shared file counts once; the mixed one is still referenced.
+        let report = lib.gc(&f, true, true);
+        assert_eq!(report.reclaimable_bytes, 6);
+
+        let report = lib.gc(&f, false, true);
+        assert_eq!((report.deleted_files, report.deleted_assets), (1, 3));
+        assert!(!f.store.contains(&shared.relative_path));
+        assert!(f.store.contains(&mixed.relative_path));
+        assert_eq!(lib.asset_ids(), [on_scene]);
+    }
+}
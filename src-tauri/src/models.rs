@@ -1,8 +1,392 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    time::Duration,
+};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
+    Pending,
+    Running,
+    /// Cancellation was sent to the worker but not yet acknowledged.
+    Cancelling,
+    Completed,
+    Failed,
+    Cancelled,
+    Interrupted,
+}
+
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Cancelling => "cancelling",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Interrupted => "interrupted",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pending" => Some(Self::Pending),
+            "running" => Some(Self::Running),
+            "cancelling" => Some(Self::Cancelling),
+            "completed" => Some(Self::Completed),
+            "failed" => Some(Self::Failed),
+            "cancelled" => Some(Self::Cancelled),
+            "interrupted" => Some(Self::Interrupted),
+            _ => None,
+        }
+    }
+
+    /// Whether the task has reached a final state.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::Failed | Self::Cancelled | Self::Interrupted
+        )
+    }
+}
+
+/// Queued tasks are dispatched highest priority first, oldest first within
+/// the same priority.
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl TaskPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::High => "high",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "low" => Some(Self::Low),
+            "normal" => Some(Self::Normal),
+            "high" => Some(Self::High),
+            _ => None,
+        }
+    }
+}
+
+/// How many tasks the host hands to the worker at once. Everything beyond
+/// these limits waits in the dispatcher's queue.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ConcurrencyLimits {
+    pub max_concurrent: u32,
+    /// Lower caps for individual task types, on top of `max_concurrent`.
+    #[serde(default)]
+    pub per_task_type: BTreeMap<String, u32>,
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 2,
+            per_task_type: BTreeMap::new(),
+        }
+    }
+}
+
+/// Watchdog limits for a running task. `None` disables a check.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskTimeout {
+    /// Longest a task may run, counted from `task.started`.
+    pub max_runtime_secs: Option<u64>,
+    /// Longest a task may go without starting or reporting progress.
+    pub stall_secs: Option<u64>,
+}
+
+impl Default for TaskTimeout {
+    fn default() -> Self {
+        Self {
+            max_runtime_secs: Some(30 * 60),
+            stall_secs: Some(5 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct TaskTimeouts {
+    #[serde(default)]
+    pub default: TaskTimeout,
+    /// Replaces `default` for individual task types.
+    #[serde(default)]
+    pub per_task_type: BTreeMap<String, TaskTimeout>,
+}
+
+impl TaskTimeouts {
+    pub fn for_task_type(&self, task_type: &str) -> &TaskTimeout {
+        self.per_task_type.get(task_type).unwrap_or(&self.default)
+    }
+}
+
+/// How the worker process is started. Saved changes apply on the next
+/// start; a running worker keeps its current configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkerLaunchConfig {
+    #[serde(default)]
+    pub program: WorkerProgram,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Added to the app's own environment.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Defaults to the app's working directory.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WorkerProgram {
+    /// A binary bundled with the app under `bundle.externalBin`.
+    Sidecar { name: String },
+    /// Any executable on disk, such as a development build of the worker.
+    Executable { path: String },
+}
+
+impl Default for WorkerProgram {
+    fn default() -> Self {
+        Self::Sidecar {
+            name: "dy-worker".into(),
+        }
+    }
+}
+
+/// When the manager declares a worker hung, how long it waits before
+/// restarting one, and how many restarts it tolerates before giving up.
+/// Saved changes apply right away.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkerRestartPolicy {
+    /// Delay before each consecutive restart; the last one repeats.
+    pub restart_backoff_ms: Vec<u64>,
+    /// Restarts are counted over this window. An open circuit breaker also
+    /// turns half-open once the window has passed.
+    pub circuit_breaker_window_secs: u64,
+    /// Restarts tolerated within the window before the breaker opens.
+    pub circuit_breaker_max_restarts: u32,
+    pub heartbeat_interval_ms: u64,
+    /// Consecutive missed intervals before the worker counts as hung.
+    pub heartbeat_max_misses: u8,
+}
+
+impl Default for WorkerRestartPolicy {
+    fn default() -> Self {
+        Self {
+            restart_backoff_ms: vec![1_000, 2_000, 4_000, 8_000, 16_000, 30_000],
+            circuit_breaker_window_secs: 10 * 60,
+            circuit_breaker_max_restarts: 5,
+            heartbeat_interval_ms: 2_000,
+            heartbeat_max_misses: 3,
+        }
+    }
+}
+
+impl WorkerRestartPolicy {
+    /// Delay before the restart at `step`; steps past the end reuse the last.
+    pub fn restart_backoff(&self, step: usize) -> Duration {
+        let last = self.restart_backoff_ms.len().saturating_sub(1);
+        self.restart_backoff_ms
+            .get(step.min(last))
+            .copied()
+            .map(Duration::from_millis)
+            .unwrap_or_default()
+    }
+
+    pub fn circuit_breaker_window(&self) -> Duration {
+        Duration::from_secs(self.circuit_breaker_window_secs)
+    }
+
+    pub fn heartbeat_interval(&self) -> Duration {
+        Duration::from_millis(self.heartbeat_interval_ms)
+    }
+}
+
+/// How many worker processes run side by side. Saved changes apply the next
+/// time the whole pool is started.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkerPoolConfig {
+    /// Workers that take every task type not claimed by a group.
+    pub size: u32,
+    #[serde(default)]
+    pub groups: Vec<WorkerGroupConfig>,
+}
+
+impl Default for WorkerPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: 1,
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl WorkerPoolConfig {
+    /// The group of each worker in pool order: the general pool first, then
+    /// every group in turn.
+    pub fn slots(&self) -> Vec<Option<String>> {
+        let general = (0..self.size).map(|_| None);
+        let grouped = self
+            .groups
+            .iter()
+            .flat_map(|group| (0..group.size).map(|_| Some(group.name.clone())));
+        general.chain(grouped).collect()
+    }
+
+    /// Maps each task type claimed by a group to that group's name.
+    pub fn routes(&self) -> HashMap<String, String> {
+        self.groups
+            .iter()
+            .flat_map(|group| {
+                group
+                    .task_types
+                    .iter()
+                    .map(|task_type| (task_type.clone(), group.name.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Workers reserved for a set of task types, so that slow tasks of one kind
+/// cannot hold up the rest.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WorkerGroupConfig {
+    pub name: String,
+    pub task_types: Vec<String>,
+    pub size: u32,
+}
+
+/// Why an attempt ended without a result. Workers report the class of a
+/// failure through `error_code` on `task.failed`; anything unrecognised is
+/// treated as a plain task failure.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    /// The worker crashed, hung or was restarted while the task was running.
+    WorkerLost,
+    Timeout,
+    RateLimited,
+    Network,
+    Failed,
+}
+
+impl ErrorClass {
+    pub fn from_error_code(code: Option<&str>) -> Self {
+        match code {
+            Some("timeout") => Self::Timeout,
+            Some("rate_limited") => Self::RateLimited,
+            Some("network") => Self::Network,
+            _ => Self::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts including the first one; 1 disables retries.
+    pub max_attempts: u32,
+    pub initial_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff_ms: 2_000,
+            max_backoff_ms: 60_000,
+            retry_on: vec![
+                ErrorClass::WorkerLost,
+                ErrorClass::Timeout,
+                ErrorClass::RateLimited,
+                ErrorClass::Network,
+            ],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns the delay before the next attempt, or `None` when `attempt`
+    /// (the one that just ended) was the last one allowed for `class`.
+    pub fn next_delay(&self, attempt: u32, class: ErrorClass) -> Option<Duration> {
+        if attempt >= self.max_attempts || !self.retry_on.contains(&class) {
+            return None;
+        }
+        let exponent = attempt.saturating_sub(1).min(16);
+        let delay_ms = self
+            .initial_backoff_ms
+            .saturating_mul(1u64 << exponent)
+            .min(self.max_backoff_ms);
+        Some(Duration::from_millis(delay_ms))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskRetryPolicy {
+    pub task_type: String,
+    pub policy: RetryPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub task_id: String,
+    pub task_type: String,
+    pub project_id: String,
+    pub status: TaskStatus,
+    pub priority: TaskPriority,
+    pub attempt: u32,
+    pub progress: f64,
+    pub message: Option<String>,
+    pub config: Value,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub graph: Option<TaskGraphLink>,
+    /// Shared by every task started by the same user action and carried on
+    /// each envelope sent for the task. `None` for tasks recorded before
+    /// tracing existed.
+    pub trace_id: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Places a task inside a task graph. `depends_on` lists the node keys of
+/// the tasks whose outputs it needs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaskGraphLink {
+    pub graph_id: String,
+    pub node_key: String,
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraph {
+    pub id: String,
+    pub project_id: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskGraphState {
     Pending,
     Running,
     Completed,
@@ -10,6 +394,53 @@ pub enum TaskStatus {
     Cancelled,
 }
 
+/// A task graph summarised as one unit, with its nodes in submission order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraphStatus {
+    pub graph_id: String,
+    pub project_id: String,
+    pub state: TaskGraphState,
+    pub progress: f64,
+    pub nodes: Vec<TaskInfo>,
+    pub created_at: String,
+}
+
+impl TaskGraphStatus {
+    /// A failed or interrupted node fails the whole graph; otherwise a
+    /// cancelled node cancels it.
+    pub fn new(graph: TaskGraph, nodes: Vec<TaskInfo>) -> Self {
+        let any = |status: TaskStatus| nodes.iter().any(|node| node.status == status);
+        let all_completed = nodes
+            .iter()
+            .all(|node| node.status == TaskStatus::Completed);
+        let state = if any(TaskStatus::Failed) || any(TaskStatus::Interrupted) {
+            TaskGraphState::Failed
+        } else if any(TaskStatus::Cancelled) {
+            TaskGraphState::Cancelled
+        } else if all_completed {
+            TaskGraphState::Completed
+        } else if any(TaskStatus::Running) || any(TaskStatus::Completed) {
+            TaskGraphState::Running
+        } else {
+            TaskGraphState::Pending
+        };
+        let progress = if nodes.is_empty() {
+            0.0
+        } else {
+            nodes.iter().map(|node| node.progress).sum::<f64>() / nodes.len() as f64
+        };
+
+        Self {
+            graph_id: graph.id,
+            project_id: graph.project_id,
+            state,
+            progress,
+            nodes,
+            created_at: graph.created_at,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Project {
     pub id: String,
@@ -36,6 +467,54 @@ pub struct UpdateProjectInput {
     pub status: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateProjectInput {
+    pub name: Option<String>,
+    /// Copies the asset rows, sharing their media store files with the
+    /// original project. Without it the copy has no assets.
+    pub share_assets: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateScene {
+    pub scene_text: String,
+    pub visual_prompt: Option<String>,
+    pub duration_ms: i64,
+    pub camera_hint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub theme: Option<String>,
+    pub target_platform: String,
+    pub scenes: Vec<TemplateScene>,
+    pub prompt_snippets: Vec<String>,
+    pub is_builtin: bool,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveProjectTemplateInput {
+    pub project_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub prompt_snippets: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateProjectFromTemplateInput {
+    pub template_id: String,
+    pub name: String,
+    pub theme: Option<String>,
+    pub target_platform: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScriptVersion {
     pub id: String,
@@ -93,3 +572,94 @@ pub struct SceneReorderItem {
     pub id: String,
     pub scene_index: i64,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub id: String,
+    pub project_id: String,
+    pub scene_id: Option<String>,
+    pub task_id: Option<String>,
+    pub asset_type: String,
+    pub provider: Option<String>,
+    pub local_path: Option<String>,
+    pub meta_json: Option<String>,
+    pub checksum: Option<String>,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateAssetInput {
+    pub project_id: String,
+    pub scene_id: Option<String>,
+    pub task_id: Option<String>,
+    pub asset_type: String,
+    pub provider: Option<String>,
+    pub local_path: Option<String>,
+    pub meta_json: Option<String>,
+    pub checksum: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct UpdateAssetInput {
+    pub scene_id: Option<String>,
+    pub provider: Option<String>,
+    pub local_path: Option<String>,
+    pub meta_json: Option<String>,
+    pub checksum: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportAssetInput {
+    pub project_id: String,
+    pub scene_id: Option<String>,
+    pub asset_type: String,
+    pub source_path: String,
+    pub provider: Option<String>,
+    pub meta_json: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetGcInput {
+    pub dry_run: bool,
+    pub purge_sceneless: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredFileEntry {
+    pub relative_path: String,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetGcReport {
+    pub dry_run: bool,
+    pub unreferenced_files: Vec<StoredFileEntry>,
+    pub missing_file_assets: Vec<Asset>,
+    pub sceneless_assets: Vec<Asset>,
+    pub reclaimable_bytes: u64,
+    pub deleted_files: usize,
+    pub deleted_assets: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectExportReport {
+    pub path: String,
+    pub format_version: u32,
+    pub script_versions: usize,
+    pub scenes: usize,
+    pub assets: usize,
+    pub media_files: usize,
+    pub missing_media_files: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsExportReport {
+    pub path: String,
+    pub log_files: usize,
+    pub restarts: usize,
+    pub envelopes: usize,
+    /// Whether `PRAGMA integrity_check` came back clean.
+    pub database_intact: bool,
+}
@@ -0,0 +1,703 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+};
+
+use chrono::Utc;
+use rusqlite::{Connection, Transaction};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    error::{AppError, AppResult, ErrorCode},
+    media_store::{self, MediaStore, StoredFile},
+    models::{
+        Asset, DuplicateProjectInput, Project, ProjectExportReport, ScriptVersion, StoryboardScene,
+    },
+    repository::{assets_repo, projects_repo, scenes_repo, scripts_repo},
+};
+
+/// Bumped whenever the layout of a bundle changes in a way older readers
+/// cannot handle. Bundles with any other version are rejected on import.
+pub const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DATA_ENTRY: &str = "project.json";
+const MEDIA_PREFIX: &str = "media/";
+
+const DUPLICATE_STATUS: &str = "draft";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    format_version: u32,
+    app_version: String,
+    exported_at: String,
+    source_project_id: String,
+    /// Store-relative paths of the media files included under `media/`.
+    media_files: Vec<String>,
+}
+
+/// Every row that belongs to a project, as written to `project.json`.
+/// Tasks are not part of a bundle; `assets.task_id` is dropped on import.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    project: Project,
+    script_versions: Vec<ScriptVersion>,
+    scenes: Vec<StoryboardScene>,
+    assets: Vec<Asset>,
+}
+
+/// A bundle whose media has already been copied into the store and which is
+/// ready to be inserted by `restore_project`.
+pub struct ImportedBundle {
+    snapshot: ProjectSnapshot,
+    stored_media: HashMap<String, StoredFile>,
+}
+
+pub fn load_project(
+    conn: &Connection,
+    project_id: &str,
+) -> rusqlite::Result<Option<ProjectSnapshot>> {
+    let Some(project) = projects_repo::get_by_id(conn, project_id)? else {
+        return Ok(None);
+    };
+    Ok(Some(ProjectSnapshot {
+        project,
+        script_versions: scripts_repo::list_by_project(conn, project_id)?,
+        scenes: scenes_repo::list_by_project(conn, project_id)?,
+        assets: assets_repo::list_by_project(conn, project_id)?,
+    }))
+}
+
+/// Writes `snapshot` and the store files its assets reference to a zip
+/// archive at `dest`. Assets whose file is missing are exported without it.
+pub fn write_bundle(
+    store: &MediaStore,
+    snapshot: &ProjectSnapshot,
+    dest: &Path,
+) -> AppResult<ProjectExportReport> {
+    let media_files: BTreeSet<&str> = snapshot
+        .assets
+        .iter()
+        .filter_map(|asset| asset.local_path.as_deref())
+        .filter(|path| store.contains(path))
+        .collect();
+    let missing_media_files = snapshot
+        .assets
+        .iter()
+        .filter_map(|asset| asset.local_path.as_deref())
+        .filter(|path| !media_files.contains(path))
+        .count();
+
+    let manifest = BundleManifest {
+        format_version: BUNDLE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: Utc::now().to_rfc3339(),
+        source_project_id: snapshot.project.id.clone(),
+        media_files: media_files.iter().map(|path| path.to_string()).collect(),
+    };
+
+    // Write next to the destination first so a failed export never leaves a
+    // truncated bundle under the requested name.
+    let temp = partial_path(dest);
+    if let Err(err) = write_archive(store, &manifest, snapshot, &temp)
+        .and_then(|_| fs::rename(&temp, dest).map_err(|e| e.to_string()))
+    {
+        let _ = fs::remove_file(&temp);
+        return Err(AppError::io(
+            format_args!("failed to write bundle {}", dest.display()),
+            err,
+        ));
+    }
+
+    Ok(ProjectExportReport {
+        path: dest.to_string_lossy().into_owned(),
+        format_version: BUNDLE_FORMAT_VERSION,
+        script_versions: snapshot.script_versions.len(),
+        scenes: snapshot.scenes.len(),
+        assets: snapshot.assets.len(),
+        media_files: manifest.media_files.len(),
+        missing_media_files,
+    })
+}
+
+/// Opens the bundle at `source`, checks its format version and copies its
+/// media into the store. No database rows are touched yet, so this can run
+/// without holding the connection lock.
+pub fn read_bundle(store: &MediaStore, source: &Path) -> AppResult<ImportedBundle> {
+    let file = File::open(source).map_err(|e| {
+        AppError::io(
+            format_args!("failed to open bundle {}", source.display()),
+            e,
+        )
+    })?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| unsupported(format!("{} is not a project bundle: {e}", source.display())))?;
+
+    let manifest: Value = read_json_entry(&mut archive, MANIFEST_ENTRY)?;
+    let format_version = manifest.get("format_version").and_then(Value::as_u64);
+    if format_version != Some(u64::from(BUNDLE_FORMAT_VERSION)) {
+        return Err(unsupported(match format_version {
+            Some(version) => format!(
+                "bundle format version {version} is not supported (expected {BUNDLE_FORMAT_VERSION})"
+            ),
+            None => "bundle manifest has no format_version".into(),
+        }));
+    }
+    let manifest: BundleManifest = serde_json::from_value(manifest)
+        .map_err(|e| unsupported(format!("invalid bundle manifest: {e}")))?;
+    let snapshot: ProjectSnapshot = read_json_entry(&mut archive, DATA_ENTRY)?;
+
+    let mut stored_media = HashMap::with_capacity(manifest.media_files.len());
+    for path in manifest.media_files {
+        let mut entry = archive
+            .by_name(&format!("{MEDIA_PREFIX}{path}"))
+            .map_err(|e| unsupported(format!("bundle is missing media file {path}: {e}")))?;
+        let stored = store
+            .import_reader(&mut entry, Path::new(&path).extension())
+            .map_err(|e| AppError::io(format_args!("failed to import media file {path}"), e))?;
+        stored_media.insert(path, stored);
+    }
+
+    Ok(ImportedBundle {
+        snapshot,
+        stored_media,
+    })
+}
+
+/// Inserts the bundle's rows as a new project. Assets whose file was not in
+/// the bundle keep their row but lose their `local_path`.
+pub fn restore_project(conn: &Connection, bundle: ImportedBundle) -> AppResult<Project> {
+    let ImportedBundle {
+        snapshot,
+        stored_media,
+    } = bundle;
+
+    let project = Project {
+        id: Uuid::new_v4().to_string(),
+        updated_at: Utc::now().to_rfc3339(),
+        ..snapshot.project.clone()
+    };
+    let tx = conn.unchecked_transaction()?;
+    let project = insert_copy(
+        &tx,
+        project,
+        snapshot,
+        |local_path, checksum| match local_path.and_then(|path| stored_media.get(&path)) {
+            Some(stored) => (
+                Some(stored.relative_path.clone()),
+                Some(stored.checksum.clone()),
+            ),
+            None => (None, checksum),
+        },
+    )?;
+    tx.commit()?;
+    Ok(project)
+}
+
+/// Deep-copies a project as a new draft. Script versions keep their numbers
+/// and scenes their order. With `share_assets`, the copied assets reference
+/// the same media store files as the originals. The store is
+/// content-addressed, so a copied file would land at the very same path:
+/// sharing is copying, and deleting either project leaves the other's media
+/// intact.
+///
+/// `adopted` holds files of the project's assets that
+/// `media_store::import_external_files` has already copied into the store;
+/// those assets are pointed at the store in the same transaction, so the
+/// original and the copy share the file.
+pub fn duplicate_project(
+    conn: &Connection,
+    project_id: &str,
+    input: DuplicateProjectInput,
+    adopted: &HashMap<String, StoredFile>,
+) -> rusqlite::Result<Option<Project>> {
+    let tx = conn.unchecked_transaction()?;
+    let Some(mut snapshot) = load_project(&tx, project_id)? else {
+        return Ok(None);
+    };
+    if input.share_assets {
+        for asset in &mut snapshot.assets {
+            let Some(stored) = asset.local_path.as_ref().and_then(|p| adopted.get(p)) else {
+                continue;
+            };
+            media_store::adopt_asset(&tx, &asset.id, stored)?;
+            asset.local_path = Some(stored.relative_path.clone());
+            asset.checksum = Some(stored.checksum.clone());
+        }
+    } else {
+        snapshot.assets.clear();
+    }
+
+    let now = Utc::now().to_rfc3339();
+    let project = Project {
+        id: Uuid::new_v4().to_string(),
+        name: input
+            .name
+            .unwrap_or_else(|| format!("{} (copy)", snapshot.project.name)),
+        status: DUPLICATE_STATUS.into(),
+        created_at: now.clone(),
+        updated_at: now,
+        ..snapshot.project.clone()
+    };
+    let project = insert_copy(&tx, project, snapshot, |local_path, checksum| {
+        (local_path, checksum)
+    })?;
+    tx.commit()?;
+    Ok(Some(project))
+}
+
+/// Inserts `project` and copies of the snapshot's child rows under fresh
+/// IDs, remapping every foreign key to the new rows. References to rows
+/// outside the snapshot are cleared. `relink` maps each asset's
+/// `(local_path, checksum)` to the values stored on its copy. The caller
+/// owns the transaction.
+fn insert_copy<F>(
+    tx: &Transaction<'_>,
+    project: Project,
+    snapshot: ProjectSnapshot,
+    relink: F,
+) -> rusqlite::Result<Project>
+where
+    F: Fn(Option<String>, Option<String>) -> (Option<String>, Option<String>),
+{
+    let script_ids: HashMap<String, String> = snapshot
+        .script_versions
+        .iter()
+        .map(|sv| (sv.id.clone(), Uuid::new_v4().to_string()))
+        .collect();
+    let scene_ids: HashMap<String, String> = snapshot
+        .scenes
+        .iter()
+        .map(|scene| (scene.id.clone(), Uuid::new_v4().to_string()))
+        .collect();
+
+    projects_repo::insert(tx, &project)?;
+
+    for sv in snapshot.script_versions {
+        let sv = ScriptVersion {
+            id: script_ids[&sv.id].clone(),
+            project_id: project.id.clone(),
+            ..sv
+        };
+        scripts_repo::insert(tx, &sv)?;
+    }
+
+    for scene in snapshot.scenes {
+        let scene = StoryboardScene {
+            id: scene_ids[&scene.id].clone(),
+            project_id: project.id.clone(),
+            script_version_id: scene
+                .script_version_id
+                .and_then(|id| script_ids.get(&id).cloned()),
+            ..scene
+        };
+        scenes_repo::insert(tx, &scene)?;
+    }
+
+    for asset in snapshot.assets {
+        let (local_path, checksum) = relink(asset.local_path, asset.checksum);
+        let asset = Asset {
+            id: Uuid::new_v4().to_string(),
+            project_id: project.id.clone(),
+            scene_id: asset.scene_id.and_then(|id| scene_ids.get(&id).cloned()),
+            task_id: None,
+            local_path,
+            checksum,
+            ..asset
+        };
+        assets_repo::insert(tx, &asset)?;
+    }
+
+    Ok(project)
+}
+
+fn write_archive(
+    store: &MediaStore,
+    manifest: &BundleManifest,
+    snapshot: &ProjectSnapshot,
+    target: &Path,
+) -> Result<(), String> {
+    let file = File::create(target).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let deflated = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    // Images, audio and video are already compressed.
+    let stored = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Stored)
+        .large_file(true);
+
+    write_json_entry(&mut zip, MANIFEST_ENTRY, manifest, deflated)?;
+    write_json_entry(&mut zip, DATA_ENTRY, snapshot, deflated)?;
+
+    for path in &manifest.media_files {
+        let absolute = store
+            .resolve(path)
+            .ok_or_else(|| format!("invalid media path {path}"))?;
+        let mut source = File::open(&absolute).map_err(|e| format!("{path}: {e}"))?;
+        zip.start_file(format!("{MEDIA_PREFIX}{path}"), stored)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut source, &mut zip).map_err(|e| format!("{path}: {e}"))?;
+    }
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn write_json_entry<T: Serialize>(
+    zip: &mut ZipWriter<File>,
+    name: &str,
+    value: &T,
+    options: SimpleFileOptions,
+) -> Result<(), String> {
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    serde_json::to_writer_pretty(zip, value).map_err(|e| format!("{name}: {e}"))
+}
+
+fn read_json_entry<T: for<'de> Deserialize<'de>>(
+    archive: &mut ZipArchive<File>,
+    name: &str,
+) -> AppResult<T> {
+    let entry = archive.by_name(name).map_err(|e| match e {
+        ZipError::FileNotFound => unsupported(format!("bundle is missing {name}")),
+        other => unsupported(format!("failed to read {name}: {other}")),
+    })?;
+    serde_json::from_reader(entry).map_err(|e| unsupported(format!("invalid {name}: {e}")))
+}
+
+fn unsupported(message: String) -> AppError {
+    AppError::new(ErrorCode::UnsupportedFormat, message)
+}
+
+pub fn partial_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::{
+        database::{self, SharedConnection},
+        models::{
+            CreateAssetInput, CreateProjectInput, CreateSceneInput, CreateScriptVersionInput,
+        },
+    };
+
+    struct Fixture {
+        dir: PathBuf,
+        db: SharedConnection,
+        store: MediaStore,
+        project_id: String,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    /// A project with two script versions, a scene on the second one and
+    /// an asset on that scene whose file is in the store.
+    fn fixture() -> Fixture {
+        let dir = env::temp_dir().join(format!("dy-bundle-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = MediaStore::open(dir.join("media")).unwrap();
+        let source = dir.join("cover.png");
+        fs::write(&source, b"not really a png").unwrap();
+        let stored = store.import_file(&source).unwrap();
+
+        let db = database::open_in_memory();
+        let project_id = {
+            let conn = db.lock().unwrap();
+            let project = projects_repo::create(
+                &conn,
+                CreateProjectInput {
+                    name: "bundle test".into(),
+                    theme: Some("food".into()),
+                    target_platform: None,
+                },
+            )
+            .unwrap();
+            let mut script = None;
+            for content in ["first draft", "second draft"] {
+                let input = CreateScriptVersionInput {
+                    project_id: project.id.clone(),
+                    content: content.into(),
+                    source: Some("manual".into()),
+                    model: None,
+                    prompt_snapshot: None,
+                };
+                script = Some(scripts_repo::create(&conn, input).unwrap());
+            }
+            let scene = scenes_repo::create(
+                &conn,
+                CreateSceneInput {
+                    project_id: project.id.clone(),
+                    script_version_id: script.map(|sv| sv.id),
+                    scene_text: "opening".into(),
+                    visual_prompt: None,
+                    duration_ms: Some(2_000),
+                    camera_hint: None,
+                },
+            )
+            .unwrap();
+            assets_repo::create(
+                &conn,
+                CreateAssetInput {
+                    project_id: project.id.clone(),
+                    scene_id: Some(scene.id),
+                    task_id: None,
+                    asset_type: "image".into(),
+                    provider: None,
+                    local_path: Some(stored.relative_path),
+                    meta_json: None,
+                    checksum: Some(stored.checksum),
+                    status: Some("ready".into()),
+                },
+            )
+            .unwrap();
+            project.id
+        };
+        Fixture {
+            dir,
+            db,
+            store,
+            project_id,
+        }
+    }
+
+    impl Fixture {
+        fn snapshot(&self, project_id: &str) -> ProjectSnapshot {
+            let conn = self.db.lock().unwrap();
+            load_project(&conn, project_id).unwrap().unwrap()
+        }
+
+        fn export(&self) -> PathBuf {
+            let dest = self.dir.join("project.dyproj");
+            write_bundle(&self.store, &self.snapshot(&self.project_id), &dest).unwrap();
+            dest
+        }
+
+        /// Writes an archive with the given manifest, the fixture project's
+        /// data and no media.
+        fn write_archive_with(&self, manifest: Option<Value>) -> PathBuf {
+            let dest = self.dir.join(format!("{}.dyproj", Uuid::new_v4()));
+            let mut zip = ZipWriter::new(File::create(&dest).unwrap());
+            let options = SimpleFileOptions::default();
+            if let Some(manifest) = manifest {
+                write_json_entry(&mut zip, MANIFEST_ENTRY, &manifest, options).unwrap();
+            }
+            let snapshot = self.snapshot(&self.project_id);
+            write_json_entry(&mut zip, DATA_ENTRY, &snapshot, options).unwrap();
+            zip.finish().unwrap();
+            dest
+        }
+    }
+
+    fn manifest(format_version: u32, media_files: &[&str]) -> Value {
+        serde_json::json!({
+            "format_version": format_version,
+            "app_version": "0.0.0",
+            "exported_at": "2024-01-01T00:00:00Z",
+            "source_project_id": "p1",
+            "media_files": media_files,
+        })
+    }
+
+    fn import_error(f: &Fixture, bundle: &Path) -> AppError {
+        match read_bundle(&f.store, bundle) {
+            Ok(_) => panic!("bundle was accepted"),
+            Err(err) => err,
+        }
+    }
+
+    #[test]
+    fn export_then_import_remaps_every_row() {
+        let f = fixture();
+        let bundle = f.export();
+        let original = f.snapshot(&f.project_id);
+
+        // Import into an empty store so the media must come from the bundle.
+        let target_store = MediaStore::open(f.dir.join("other-media")).unwrap();
+        let imported = read_bundle(&target_store, &bundle).unwrap();
+        let project = {
+            let conn = f.db.lock().unwrap();
+            restore_project(&conn, imported).unwrap()
+        };
+        let copy = f.snapshot(&project.id);
+
+        assert_ne!(project.id, original.project.id);
+        assert_eq!(project.name, original.project.name);
+        let versions: Vec<i64> = copy
+            .script_versions
+            .iter()
+            .map(|sv| sv.version_no)
+            .collect();
+        assert_eq!(versions, [2, 1]);
+        for sv in &copy.script_versions {
+            assert!(original.script_versions.iter().all(|o| o.id != sv.id));
+        }
+
+        let scene = &copy.scenes[0];
+        assert_ne!(scene.id, original.scenes[0].id);
+        let latest = &copy.script_versions[0];
+        assert_eq!(scene.script_version_id.as_deref(), Some(latest.id.as_str()));
+
+        let asset = &copy.assets[0];
+        assert_ne!(asset.id, original.assets[0].id);
+        assert_eq!(asset.scene_id.as_deref(), Some(scene.id.as_str()));
+        assert_eq!(asset.checksum, original.assets[0].checksum);
+        let path = asset.local_path.as_deref().unwrap();
+        assert!(target_store.contains(path));
+        let content = fs::read(target_store.resolve(path).unwrap()).unwrap();
+        assert_eq!(content, b"not really a png");
+    }
+
+    #[test]
+    fn other_format_versions_are_rejected() {
+        let f = fixture();
+        let bundle = f.write_archive_with(Some(manifest(BUNDLE_FORMAT_VERSION + 1, &[])));
+
+        let err = import_error(&f, &bundle);
+        assert_eq!(err.code, ErrorCode::UnsupportedFormat);
+        assert!(err.message.contains("not supported"), "{}", err.message);
+    }
+
+    #[test]
+    fn bundle_without_manifest_is_rejected() {
+        let f = fixture();
+        let bundle = f.write_archive_with(None);
+
+        let err = import_error(&f, &bundle);
+        assert_eq!(err.code, ErrorCode::UnsupportedFormat);
+        assert!(err.message.contains(MANIFEST_ENTRY), "{}", err.message);
+    }
+
+    #[test]
+    fn listed_media_missing_from_archive_is_rejected() {
+        let f = fixture();
+        let listed = "ab/abcdef.png";
+        let bundle = f.write_archive_with(Some(manifest(BUNDLE_FORMAT_VERSION, &[listed])));
+
+        let err = import_error(&f, &bundle);
+        assert_eq!(err.code, ErrorCode::UnsupportedFormat);
+        assert!(err.message.contains(listed), "{}", err.message);
+    }
+
+    #[test]
+    fn duplicate_keeps_script_numbers_and_remaps_scenes() {
+        let f = fixture();
+        let original = f.snapshot(&f.project_id);
+        let input = DuplicateProjectInput {
+            name: None,
+            share_assets: true,
+        };
+        let project = {
+            let conn = f.db.lock().unwrap();
+            duplicate_project(&conn, &f.project_id, input, &HashMap::new())
+                .unwrap()
+                .unwrap()
+        };
+        let copy = f.snapshot(&project.id);
+
+        assert_eq!(project.name, "bundle test (copy)");
+        assert_eq!(project.status, DUPLICATE_STATUS);
+        let numbers = |snapshot: &ProjectSnapshot| -> Vec<(i64, String)> {
+            snapshot
+                .script_versions
+                .iter()
+                .map(|sv| (sv.version_no, sv.content.clone()))
+                .collect()
+        };
+        assert_eq!(numbers(&copy), numbers(&original));
+
+        let scene = &copy.scenes[0];
+        let second = copy.script_versions.iter().find(|sv| sv.version_no == 2);
+        assert_eq!(
+            scene.script_version_id.as_deref(),
+            second.map(|sv| sv.id.as_str())
+        );
+        assert_ne!(scene.id, original.scenes[0].id);
+        assert_eq!(copy.assets[0].scene_id.as_deref(), Some(scene.id.as_str()));
+        assert_eq!(copy.assets[0].local_path, original.assets[0].local_path);
+    }
+
+    #[test]
+    fn duplicate_shares_adopted_external_files() {
+        let f = fixture();
+        let external = f.dir.join("voice.mp3");
+        fs::write(&external, b"external audio").unwrap();
+        let external_path = external.to_string_lossy().into_owned();
+        {
+            let conn = f.db.lock().unwrap();
+            let input = CreateAssetInput {
+                project_id: f.project_id.clone(),
+                scene_id: None,
+                task_id: None,
+                asset_type: "audio".into(),
+                provider: None,
+                local_path: Some(external_path.clone()),
+                meta_json: None,
+                checksum: None,
+                status: None,
+            };
+            assets_repo::create(&conn, input).unwrap();
+        }
+
+        let assets = f.snapshot(&f.project_id).assets;
+        let adopted = media_store::import_external_files(&f.store, &assets);
+        assert_eq!(adopted.len(), 1);
+        let input = DuplicateProjectInput {
+            name: Some("copy".into()),
+            share_assets: true,
+        };
+        let project = {
+            let conn = f.db.lock().unwrap();
+            duplicate_project(&conn, &f.project_id, input, &adopted)
+                .unwrap()
+                .unwrap()
+        };
+
+        let stored = &adopted[&external_path];
+        for project_id in [&f.project_id, &project.id] {
+            let audio = f
+                .snapshot(project_id)
+                .assets
+                .into_iter()
+                .find(|asset| asset.asset_type == "audio")
+                .unwrap();
+            assert_eq!(audio.local_path.as_ref(), Some(&stored.relative_path));
+            assert_eq!(audio.checksum.as_ref(), Some(&stored.checksum));
+        }
+        assert!(f.store.contains(&stored.relative_path));
+    }
+
+    #[test]
+    fn duplicate_without_sharing_drops_assets() {
+        let f = fixture();
+        let input = DuplicateProjectInput {
+            name: None,
+            share_assets: false,
+        };
+        let project = {
+            let conn = f.db.lock().unwrap();
+            duplicate_project(&conn, &f.project_id, input, &HashMap::new())
+                .unwrap()
+                .unwrap()
+        };
+
+        let copy = f.snapshot(&project.id);
+        assert!(copy.assets.is_empty());
+        assert_eq!(copy.scenes.len(), 1);
+        assert_eq!(f.snapshot(&f.project_id).assets.len(), 1);
+    }
+}
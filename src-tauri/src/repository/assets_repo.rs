@@ -0,0 +1,151 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::models::{Asset, CreateAssetInput, UpdateAssetInput};
+
+pub const ASSET_TYPES: [&str; 4] = ["image", "video", "audio", "subtitle"];
+
+const DEFAULT_STATUS: &str = "pending";
+
+pub fn create(conn: &Connection, input: CreateAssetInput) -> rusqlite::Result<Asset> {
+    let asset = Asset {
+        id: Uuid::new_v4().to_string(),
+        project_id: input.project_id,
+        scene_id: input.scene_id,
+        task_id: input.task_id,
+        asset_type: input.asset_type,
+        provider: input.provider,
+        local_path: input.local_path,
+        meta_json: input.meta_json,
+        checksum: input.checksum,
+        status: input.status.unwrap_or_else(|| DEFAULT_STATUS.into()),
+    };
+    insert(conn, &asset)?;
+    Ok(asset)
+}
+
+/// Inserts a fully formed row, e.g. one restored from a project bundle.
+pub fn insert(conn: &Connection, asset: &Asset) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO assets
+         (id, project_id, scene_id, task_id, type, provider, local_path, meta_json, checksum, status)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            &asset.id,
+            &asset.project_id,
+            asset.scene_id.as_deref(),
+            asset.task_id.as_deref(),
+            &asset.asset_type,
+            asset.provider.as_deref(),
+            asset.local_path.as_deref(),
+            asset.meta_json.as_deref(),
+            asset.checksum.as_deref(),
+            &asset.status
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_all(conn: &Connection) -> rusqlite::Result<Vec<Asset>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, scene_id, task_id, type, provider, local_path, meta_json, checksum, status
+         FROM assets ORDER BY rowid ASC",
+    )?;
+    let rows = stmt.query_map([], row_to_asset)?;
+    rows.collect()
+}
+
+pub fn list_by_project(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<Asset>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, scene_id, task_id, type, provider, local_path, meta_json, checksum, status
+         FROM assets WHERE project_id = ?1 ORDER BY rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![project_id], row_to_asset)?;
+    rows.collect()
+}
+
+pub fn list_by_scene(conn: &Connection, scene_id: &str) -> rusqlite::Result<Vec<Asset>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, scene_id, task_id, type, provider, local_path, meta_json, checksum, status
+         FROM assets WHERE scene_id = ?1 ORDER BY rowid ASC",
+    )?;
+    let rows = stmt.query_map(params![scene_id], row_to_asset)?;
+    rows.collect()
+}
+
+pub fn get_by_id(conn: &Connection, id: &str) -> rusqlite::Result<Option<Asset>> {
+    conn.prepare(
+        "SELECT id, project_id, scene_id, task_id, type, provider, local_path, meta_json, checksum, status
+         FROM assets WHERE id = ?1",
+    )?
+    .query_row(params![id], row_to_asset)
+    .optional()
+}
+
+pub fn update(
+    conn: &Connection,
+    id: &str,
+    input: UpdateAssetInput,
+) -> rusqlite::Result<Option<Asset>> {
+    let mut asset = match get_by_id(conn, id)? {
+        Some(a) => a,
+        None => return Ok(None),
+    };
+
+    if let Some(scene_id) = input.scene_id {
+        asset.scene_id = Some(scene_id);
+    }
+    if let Some(provider) = input.provider {
+        asset.provider = Some(provider);
+    }
+    if let Some(path) = input.local_path {
+        asset.local_path = Some(path);
+    }
+    if let Some(meta) = input.meta_json {
+        asset.meta_json = Some(meta);
+    }
+    if let Some(checksum) = input.checksum {
+        asset.checksum = Some(checksum);
+    }
+    if let Some(status) = input.status {
+        asset.status = status;
+    }
+
+    conn.execute(
+        "UPDATE assets
+         SET scene_id = ?1, provider = ?2, local_path = ?3, meta_json = ?4,
+             checksum = ?5, status = ?6
+         WHERE id = ?7",
+        params![
+            asset.scene_id.as_deref(),
+            asset.provider.as_deref(),
+            asset.local_path.as_deref(),
+            asset.meta_json.as_deref(),
+            asset.checksum.as_deref(),
+            &asset.status,
+            &asset.id
+        ],
+    )?;
+
+    Ok(Some(asset))
+}
+
+pub fn delete(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let affected = conn.execute("DELETE FROM assets WHERE id = ?1", params![id])?;
+    Ok(affected > 0)
+}
+
+fn row_to_asset(row: &Row<'_>) -> rusqlite::Result<Asset> {
+    Ok(Asset {
+        id: row.get("id")?,
+        project_id: row.get("project_id")?,
+        scene_id: row.get("scene_id")?,
+        task_id: row.get("task_id")?,
+        asset_type: row.get("type")?,
+        provider: row.get("provider")?,
+        local_path: row.get("local_path")?,
+        meta_json: row.get("meta_json")?,
+        checksum: row.get("checksum")?,
+        status: row.get("status")?,
+    })
+}
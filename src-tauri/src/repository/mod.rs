@@ -1,3 +1,9 @@
+pub mod assets_repo;
 pub mod projects_repo;
+pub mod retry_policies_repo;
 pub mod scenes_repo;
 pub mod scripts_repo;
+pub mod task_graphs_repo;
+pub mod tasks_repo;
+pub mod templates_repo;
+pub mod worker_settings_repo;
@@ -15,7 +15,12 @@ pub fn create(conn: &Connection, input: CreateProjectInput) -> rusqlite::Result<
         created_at: now.clone(),
         updated_at: now,
     };
+    insert(conn, &project)?;
+    Ok(project)
+}
 
+/// Inserts a fully formed row, e.g. one restored from a project bundle.
+pub fn insert(conn: &Connection, project: &Project) -> rusqlite::Result<()> {
     conn.execute(
         "INSERT INTO projects (id, name, theme, target_platform, status, created_at, updated_at)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
@@ -29,8 +34,7 @@ pub fn create(conn: &Connection, input: CreateProjectInput) -> rusqlite::Result<
             &project.updated_at
         ],
     )?;
-
-    Ok(project)
+    Ok(())
 }
 
 pub fn list_all(conn: &Connection) -> rusqlite::Result<Vec<Project>> {
@@ -0,0 +1,76 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::models::{RetryPolicy, TaskRetryPolicy};
+
+pub fn list_all(conn: &Connection) -> rusqlite::Result<Vec<TaskRetryPolicy>> {
+    let mut stmt = conn.prepare(
+        "SELECT task_type, max_attempts, initial_backoff_ms, max_backoff_ms, retry_on_json
+         FROM task_retry_policies ORDER BY task_type ASC",
+    )?;
+    let rows = stmt.query_map([], row_to_policy)?;
+    rows.collect()
+}
+
+pub fn get(conn: &Connection, task_type: &str) -> rusqlite::Result<Option<TaskRetryPolicy>> {
+    conn.prepare(
+        "SELECT task_type, max_attempts, initial_backoff_ms, max_backoff_ms, retry_on_json
+         FROM task_retry_policies WHERE task_type = ?1",
+    )?
+    .query_row(params![task_type], row_to_policy)
+    .optional()
+}
+
+/// The policy configured for `task_type`, or the built-in default.
+pub fn policy_for(conn: &Connection, task_type: &str) -> rusqlite::Result<RetryPolicy> {
+    Ok(get(conn, task_type)?
+        .map(|configured| configured.policy)
+        .unwrap_or_default())
+}
+
+pub fn upsert(conn: &Connection, entry: &TaskRetryPolicy) -> rusqlite::Result<()> {
+    let retry_on_json =
+        serde_json::to_string(&entry.policy.retry_on).unwrap_or_else(|_| "[]".into());
+    conn.execute(
+        "INSERT INTO task_retry_policies
+         (task_type, max_attempts, initial_backoff_ms, max_backoff_ms, retry_on_json, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(task_type) DO UPDATE SET
+             max_attempts = excluded.max_attempts,
+             initial_backoff_ms = excluded.initial_backoff_ms,
+             max_backoff_ms = excluded.max_backoff_ms,
+             retry_on_json = excluded.retry_on_json,
+             updated_at = excluded.updated_at",
+        params![
+            &entry.task_type,
+            entry.policy.max_attempts,
+            entry.policy.initial_backoff_ms,
+            entry.policy.max_backoff_ms,
+            retry_on_json,
+            Utc::now().to_rfc3339()
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn delete(conn: &Connection, task_type: &str) -> rusqlite::Result<bool> {
+    let affected = conn.execute(
+        "DELETE FROM task_retry_policies WHERE task_type = ?1",
+        params![task_type],
+    )?;
+    Ok(affected > 0)
+}
+
+fn row_to_policy(row: &Row<'_>) -> rusqlite::Result<TaskRetryPolicy> {
+    let retry_on_json: String = row.get("retry_on_json")?;
+
+    Ok(TaskRetryPolicy {
+        task_type: row.get("task_type")?,
+        policy: RetryPolicy {
+            max_attempts: row.get("max_attempts")?,
+            initial_backoff_ms: row.get("initial_backoff_ms")?,
+            max_backoff_ms: row.get("max_backoff_ms")?,
+            retry_on: serde_json::from_str(&retry_on_json).unwrap_or_default(),
+        },
+    })
+}
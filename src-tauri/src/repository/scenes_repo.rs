@@ -17,7 +17,12 @@ pub fn create(conn: &Connection, input: CreateSceneInput) -> rusqlite::Result<St
         duration_ms: input.duration_ms.unwrap_or(DEFAULT_DURATION_MS).max(1),
         camera_hint: input.camera_hint,
     };
+    insert(conn, &scene)?;
+    Ok(scene)
+}
 
+/// Inserts a fully formed row, keeping its `scene_index`.
+pub fn insert(conn: &Connection, scene: &StoryboardScene) -> rusqlite::Result<()> {
     conn.execute(
         "INSERT INTO storyboard_scenes
          (id, project_id, script_version_id, scene_index, scene_text, visual_prompt, duration_ms, camera_hint)
@@ -33,8 +38,7 @@ pub fn create(conn: &Connection, input: CreateSceneInput) -> rusqlite::Result<St
             scene.camera_hint.as_deref()
         ],
     )?;
-
-    Ok(scene)
+    Ok(())
 }
 
 pub fn list_by_project(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<StoryboardScene>> {
@@ -16,7 +16,12 @@ pub fn create(conn: &Connection, input: CreateScriptVersionInput) -> rusqlite::R
         prompt_snapshot: input.prompt_snapshot,
         created_at: Utc::now().to_rfc3339(),
     };
+    insert(conn, &sv)?;
+    Ok(sv)
+}
 
+/// Inserts a fully formed row, keeping its `version_no`.
+pub fn insert(conn: &Connection, sv: &ScriptVersion) -> rusqlite::Result<()> {
     conn.execute(
         "INSERT INTO script_versions (id, project_id, version_no, content, source, model, prompt_snapshot, created_at)
          VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
@@ -31,8 +36,7 @@ pub fn create(conn: &Connection, input: CreateScriptVersionInput) -> rusqlite::R
             &sv.created_at
         ],
     )?;
-
-    Ok(sv)
+    Ok(())
 }
 
 pub fn list_by_project(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<ScriptVersion>> {
@@ -0,0 +1,66 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{TaskGraph, TaskGraphStatus},
+    repository::tasks_repo,
+};
+
+pub fn create(conn: &Connection, project_id: &str) -> rusqlite::Result<TaskGraph> {
+    let graph = TaskGraph {
+        id: Uuid::new_v4().to_string(),
+        project_id: project_id.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    conn.execute(
+        "INSERT INTO task_graphs (id, project_id, created_at) VALUES (?1, ?2, ?3)",
+        params![&graph.id, &graph.project_id, &graph.created_at],
+    )?;
+
+    Ok(graph)
+}
+
+pub fn get_by_id(conn: &Connection, id: &str) -> rusqlite::Result<Option<TaskGraph>> {
+    conn.prepare("SELECT id, project_id, created_at FROM task_graphs WHERE id = ?1")?
+        .query_row(params![id], row_to_graph)
+        .optional()
+}
+
+pub fn get_status(conn: &Connection, id: &str) -> rusqlite::Result<Option<TaskGraphStatus>> {
+    let Some(graph) = get_by_id(conn, id)? else {
+        return Ok(None);
+    };
+    let nodes = tasks_repo::list_by_graph(conn, &graph.id)?;
+    Ok(Some(TaskGraphStatus::new(graph, nodes)))
+}
+
+pub fn list_by_project(
+    conn: &Connection,
+    project_id: &str,
+) -> rusqlite::Result<Vec<TaskGraphStatus>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, project_id, created_at
+         FROM task_graphs WHERE project_id = ?1 ORDER BY created_at DESC",
+    )?;
+    let graphs = stmt
+        .query_map(params![project_id], row_to_graph)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    graphs
+        .into_iter()
+        .map(|graph| {
+            let nodes = tasks_repo::list_by_graph(conn, &graph.id)?;
+            Ok(TaskGraphStatus::new(graph, nodes))
+        })
+        .collect()
+}
+
+fn row_to_graph(row: &Row<'_>) -> rusqlite::Result<TaskGraph> {
+    Ok(TaskGraph {
+        id: row.get("id")?,
+        project_id: row.get("project_id")?,
+        created_at: row.get("created_at")?,
+    })
+}
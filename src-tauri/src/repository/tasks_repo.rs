@@ -0,0 +1,142 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, Row};
+use serde_json::Value;
+
+use crate::models::{TaskGraphLink, TaskInfo, TaskPriority, TaskStatus};
+
+const TASK_COLUMNS: &str = "id, project_id, task_type, status, priority, attempt, progress, \
+    message, config_json, output_json, error, graph_id, graph_node_key, depends_on_json, \
+    trace_id, created_at, updated_at";
+
+pub fn upsert(conn: &Connection, task: &TaskInfo) -> rusqlite::Result<()> {
+    let config_json = serde_json::to_string(&task.config).ok();
+    let output_json = task
+        .output
+        .as_ref()
+        .and_then(|output| serde_json::to_string(output).ok());
+    let depends_on_json = task
+        .graph
+        .as_ref()
+        .and_then(|link| serde_json::to_string(&link.depends_on).ok());
+
+    conn.execute(
+        "INSERT INTO ai_tasks
+         (id, project_id, task_type, status, priority, attempt, progress, message, config_json, output_json, error, graph_id, graph_node_key, depends_on_json, trace_id, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)
+         ON CONFLICT(id) DO UPDATE SET
+             status = excluded.status,
+             priority = excluded.priority,
+             attempt = excluded.attempt,
+             progress = excluded.progress,
+             message = excluded.message,
+             config_json = excluded.config_json,
+             output_json = excluded.output_json,
+             error = excluded.error,
+             updated_at = excluded.updated_at",
+        params![
+            &task.task_id,
+            &task.project_id,
+            &task.task_type,
+            task.status.as_str(),
+            task.priority.as_str(),
+            task.attempt,
+            task.progress,
+            task.message.as_deref(),
+            config_json,
+            output_json,
+            task.error.as_deref(),
+            task.graph.as_ref().map(|link| &link.graph_id),
+            task.graph.as_ref().map(|link| &link.node_key),
+            depends_on_json,
+            task.trace_id.as_deref(),
+            &task.created_at,
+            &task.updated_at
+        ],
+    )?;
+    Ok(())
+}
+
+pub fn list_all(conn: &Connection) -> rusqlite::Result<Vec<TaskInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS}
+         FROM ai_tasks ORDER BY updated_at DESC"
+    ))?;
+    let rows = stmt.query_map([], row_to_task)?;
+    rows.collect()
+}
+
+pub fn list_by_project(conn: &Connection, project_id: &str) -> rusqlite::Result<Vec<TaskInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS}
+         FROM ai_tasks WHERE project_id = ?1 ORDER BY updated_at DESC"
+    ))?;
+    let rows = stmt.query_map(params![project_id], row_to_task)?;
+    rows.collect()
+}
+
+/// Nodes of a task graph in submission order.
+pub fn list_by_graph(conn: &Connection, graph_id: &str) -> rusqlite::Result<Vec<TaskInfo>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TASK_COLUMNS}
+         FROM ai_tasks WHERE graph_id = ?1 ORDER BY created_at ASC, rowid ASC"
+    ))?;
+    let rows = stmt.query_map(params![graph_id], row_to_task)?;
+    rows.collect()
+}
+
+/// Marks tasks left pending or running by a previous app session as
+/// interrupted. Must run before the worker is started.
+pub fn mark_interrupted(conn: &Connection) -> rusqlite::Result<usize> {
+    conn.execute(
+        "UPDATE ai_tasks
+         SET status = ?1, message = NULL, error = ?2, updated_at = ?3
+         WHERE status IN ('pending', 'running', 'cancelling')",
+        params![
+            TaskStatus::Interrupted.as_str(),
+            "app closed while task was in-flight",
+            Utc::now().to_rfc3339()
+        ],
+    )
+}
+
+fn row_to_task(row: &Row<'_>) -> rusqlite::Result<TaskInfo> {
+    let status: String = row.get("status")?;
+    let priority: String = row.get("priority")?;
+    let config_json: Option<String> = row.get("config_json")?;
+    let output_json: Option<String> = row.get("output_json")?;
+    let graph_id: Option<String> = row.get("graph_id")?;
+    let graph = match graph_id {
+        Some(graph_id) => {
+            let node_key: Option<String> = row.get("graph_node_key")?;
+            let depends_on_json: Option<String> = row.get("depends_on_json")?;
+            Some(TaskGraphLink {
+                graph_id,
+                node_key: node_key.unwrap_or_default(),
+                depends_on: depends_on_json
+                    .and_then(|json| serde_json::from_str(&json).ok())
+                    .unwrap_or_default(),
+            })
+        }
+        None => None,
+    };
+
+    Ok(TaskInfo {
+        task_id: row.get("id")?,
+        task_type: row.get("task_type")?,
+        project_id: row.get("project_id")?,
+        status: TaskStatus::parse(&status).unwrap_or(TaskStatus::Failed),
+        priority: TaskPriority::parse(&priority).unwrap_or_default(),
+        attempt: row.get("attempt")?,
+        progress: row.get("progress")?,
+        message: row.get("message")?,
+        config: config_json
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or(Value::Null),
+        output: output_json.and_then(|json| serde_json::from_str(&json).ok()),
+        error: row.get("error")?,
+        graph,
+        trace_id: row.get("trace_id")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
@@ -0,0 +1,266 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        CreateProjectFromTemplateInput, CreateProjectInput, CreateSceneInput,
+        CreateScriptVersionInput, Project, ProjectTemplate, SaveProjectTemplateInput,
+        TemplateScene,
+    },
+    repository::{projects_repo, scenes_repo, scripts_repo},
+};
+
+/// Captures a project's settings and scene skeleton as a new template. When
+/// `prompt_snippets` is not given, they are taken from the latest script's
+/// prompt, see `snippets_from_prompt`.
+pub fn create_from_project(
+    conn: &Connection,
+    input: SaveProjectTemplateInput,
+) -> rusqlite::Result<Option<ProjectTemplate>> {
+    let Some(project) = projects_repo::get_by_id(conn, &input.project_id)? else {
+        return Ok(None);
+    };
+    let scenes = scenes_repo::list_by_project(conn, &project.id)?
+        .into_iter()
+        .map(|scene| TemplateScene {
+            scene_text: scene.scene_text,
+            visual_prompt: scene.visual_prompt,
+            duration_ms: scene.duration_ms,
+            camera_hint: scene.camera_hint,
+        })
+        .collect();
+    let prompt_snippets = match input.prompt_snippets {
+        Some(snippets) => snippets,
+        None => scripts_repo::get_latest(conn, &project.id)?
+            .and_then(|sv| sv.prompt_snapshot)
+            .map(|prompt| snippets_from_prompt(&prompt))
+            .unwrap_or_default(),
+    };
+
+    let now = Utc::now().to_rfc3339();
+    let template = ProjectTemplate {
+        id: Uuid::new_v4().to_string(),
+        name: input.name,
+        description: input.description,
+        category: input.category,
+        theme: project.theme,
+        target_platform: project.target_platform,
+        scenes,
+        prompt_snippets,
+        is_builtin: false,
+        created_at: now.clone(),
+        updated_at: now,
+    };
+
+    conn.execute(
+        "INSERT INTO project_templates
+         (id, name, description, category, theme, target_platform, scenes_json, prompt_snippets_json, is_builtin, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        params![
+            &template.id,
+            &template.name,
+            template.description.as_deref(),
+            template.category.as_deref(),
+            template.theme.as_deref(),
+            &template.target_platform,
+            serde_json::to_string(&template.scenes).unwrap_or_else(|_| "[]".into()),
+            serde_json::to_string(&template.prompt_snippets).unwrap_or_else(|_| "[]".into()),
+            template.is_builtin,
+            &template.created_at,
+            &template.updated_at
+        ],
+    )?;
+
+    Ok(Some(template))
+}
+
+pub fn list_all(conn: &Connection) -> rusqlite::Result<Vec<ProjectTemplate>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, description, category, theme, target_platform, scenes_json, prompt_snippets_json, is_builtin, created_at, updated_at
+         FROM project_templates ORDER BY is_builtin DESC, updated_at DESC",
+    )?;
+    let rows = stmt.query_map([], row_to_template)?;
+    rows.collect()
+}
+
+pub fn get_by_id(conn: &Connection, id: &str) -> rusqlite::Result<Option<ProjectTemplate>> {
+    conn.prepare(
+        "SELECT id, name, description, category, theme, target_platform, scenes_json, prompt_snippets_json, is_builtin, created_at, updated_at
+         FROM project_templates WHERE id = ?1",
+    )?
+    .query_row(params![id], row_to_template)
+    .optional()
+}
+
+/// Creates a project from a template. The template's prompt snippets are
+/// stored as a JSON array in the `prompt_snapshot` of an empty first script
+/// version, so saving the project as a template again keeps them, and the
+/// skeleton scenes are linked to that version.
+pub fn instantiate(
+    conn: &Connection,
+    input: CreateProjectFromTemplateInput,
+) -> rusqlite::Result<Option<Project>> {
+    let Some(template) = get_by_id(conn, &input.template_id)? else {
+        return Ok(None);
+    };
+
+    let tx = conn.unchecked_transaction()?;
+    let project = projects_repo::create(
+        &tx,
+        CreateProjectInput {
+            name: input.name,
+            theme: input.theme.or(template.theme),
+            target_platform: input.target_platform.or(Some(template.target_platform)),
+        },
+    )?;
+    let script_version_id = if template.prompt_snippets.is_empty() {
+        None
+    } else {
+        let prompt = serde_json::to_string(&template.prompt_snippets).unwrap_or_default();
+        let script = scripts_repo::create(
+            &tx,
+            CreateScriptVersionInput {
+                project_id: project.id.clone(),
+                content: String::new(),
+                source: Some("manual".into()),
+                model: None,
+                prompt_snapshot: Some(prompt),
+            },
+        )?;
+        Some(script.id)
+    };
+    for scene in template.scenes {
+        scenes_repo::create(
+            &tx,
+            CreateSceneInput {
+                project_id: project.id.clone(),
+                script_version_id: script_version_id.clone(),
+                scene_text: scene.scene_text,
+                visual_prompt: scene.visual_prompt,
+                duration_ms: Some(scene.duration_ms),
+                camera_hint: scene.camera_hint,
+            },
+        )?;
+    }
+    tx.commit()?;
+
+    Ok(Some(project))
+}
+
+/// Built-in templates are seeded by a migration and cannot be deleted.
+pub fn delete(conn: &Connection, id: &str) -> rusqlite::Result<bool> {
+    let affected = conn.execute(
+        "DELETE FROM project_templates WHERE id = ?1 AND is_builtin = 0",
+        params![id],
+    )?;
+    Ok(affected > 0)
+}
+
+/// Snippets written by `instantiate` come back as they were; any other
+/// prompt becomes a single snippet.
+fn snippets_from_prompt(prompt: &str) -> Vec<String> {
+    if let Ok(snippets) = serde_json::from_str::<Vec<String>>(prompt) {
+        return snippets;
+    }
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        Vec::new()
+    } else {
+        vec![prompt.to_string()]
+    }
+}
+
+fn row_to_template(row: &Row<'_>) -> rusqlite::Result<ProjectTemplate> {
+    let scenes_json: String = row.get("scenes_json")?;
+    let prompt_snippets_json: String = row.get("prompt_snippets_json")?;
+
+    Ok(ProjectTemplate {
+        id: row.get("id")?,
+        name: row.get("name")?,
+        description: row.get("description")?,
+        category: row.get("category")?,
+        theme: row.get("theme")?,
+        target_platform: row.get("target_platform")?,
+        scenes: serde_json::from_str(&scenes_json).unwrap_or_default(),
+        prompt_snippets: serde_json::from_str(&prompt_snippets_json).unwrap_or_default(),
+        is_builtin: row.get("is_builtin")?,
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{database, models::CreateScriptVersionInput};
+
+    fn save(conn: &Connection, project_id: &str, snippets: Option<Vec<String>>) -> ProjectTemplate {
+        let input = SaveProjectTemplateInput {
+            project_id: project_id.into(),
+            name: "saved".into(),
+            description: None,
+            category: None,
+            prompt_snippets: snippets,
+        };
+        create_from_project(conn, input).unwrap().unwrap()
+    }
+
+    fn instantiate_named(conn: &Connection, template_id: &str) -> Project {
+        let input = CreateProjectFromTemplateInput {
+            template_id: template_id.into(),
+            name: "from template".into(),
+            theme: None,
+            target_platform: None,
+        };
+        instantiate(conn, input).unwrap().unwrap()
+    }
+
+    #[test]
+    fn instantiated_scenes_link_to_the_seeded_script() {
+        let db = database::open_in_memory();
+        let conn = db.lock().unwrap();
+        let project = instantiate_named(&conn, "builtin-food-review");
+
+        let script = scripts_repo::get_latest(&conn, &project.id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(script.version_no, 1);
+        let scenes = scenes_repo::list_by_project(&conn, &project.id).unwrap();
+        assert_eq!(scenes.len(), 6);
+        for scene in &scenes {
+            assert_eq!(scene.script_version_id.as_deref(), Some(script.id.as_str()));
+        }
+    }
+
+    #[test]
+    fn multi_line_snippets_survive_a_round_trip() {
+        let db = database::open_in_memory();
+        let conn = db.lock().unwrap();
+        let source = instantiate_named(&conn, "builtin-unboxing");
+        let snippets = vec!["line one\nline two".to_string(), "single".to_string()];
+        let template = save(&conn, &source.id, Some(snippets.clone()));
+
+        let project = instantiate_named(&conn, &template.id);
+        let saved_again = save(&conn, &project.id, None);
+        assert_eq!(saved_again.prompt_snippets, snippets);
+    }
+
+    #[test]
+    fn free_form_prompt_becomes_one_snippet() {
+        let db = database::open_in_memory();
+        let conn = db.lock().unwrap();
+        let project = instantiate_named(&conn, "builtin-food-review");
+        let input = CreateScriptVersionInput {
+            project_id: project.id.clone(),
+            content: "script".into(),
+            source: Some("ai".into()),
+            model: None,
+            prompt_snapshot: Some("write a script\nabout noodles".into()),
+        };
+        scripts_repo::create(&conn, input).unwrap();
+
+        let template = save(&conn, &project.id, None);
+        assert_eq!(template.prompt_snippets, ["write a script\nabout noodles"]);
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::Utc;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::models::{
+    ConcurrencyLimits, TaskTimeouts, WorkerLaunchConfig, WorkerPoolConfig, WorkerRestartPolicy,
+};
+
+const CONCURRENCY_LIMITS_KEY: &str = "concurrency_limits";
+const TASK_TIMEOUTS_KEY: &str = "task_timeouts";
+const WORKER_LAUNCH_CONFIG_KEY: &str = "worker_launch_config";
+const WORKER_POOL_CONFIG_KEY: &str = "worker_pool_config";
+const WORKER_RESTART_POLICY_KEY: &str = "worker_restart_policy";
+
+/// The saved concurrency limits, or the built-in default.
+pub fn get_concurrency_limits(conn: &Connection) -> rusqlite::Result<ConcurrencyLimits> {
+    get_setting(conn, CONCURRENCY_LIMITS_KEY)
+}
+
+pub fn set_concurrency_limits(
+    conn: &Connection,
+    limits: &ConcurrencyLimits,
+) -> rusqlite::Result<()> {
+    set_setting(conn, CONCURRENCY_LIMITS_KEY, limits)
+}
+
+/// The saved task timeouts, or the built-in default.
+pub fn get_task_timeouts(conn: &Connection) -> rusqlite::Result<TaskTimeouts> {
+    get_setting(conn, TASK_TIMEOUTS_KEY)
+}
+
+pub fn set_task_timeouts(conn: &Connection, timeouts: &TaskTimeouts) -> rusqlite::Result<()> {
+    set_setting(conn, TASK_TIMEOUTS_KEY, timeouts)
+}
+
+/// The saved launch configuration, or the bundled sidecar.
+pub fn get_worker_launch_config(conn: &Connection) -> rusqlite::Result<WorkerLaunchConfig> {
+    get_setting(conn, WORKER_LAUNCH_CONFIG_KEY)
+}
+
+pub fn set_worker_launch_config(
+    conn: &Connection,
+    config: &WorkerLaunchConfig,
+) -> rusqlite::Result<()> {
+    set_setting(conn, WORKER_LAUNCH_CONFIG_KEY, config)
+}
+
+/// The saved pool layout, or a single general worker.
+pub fn get_worker_pool_config(conn: &Connection) -> rusqlite::Result<WorkerPoolConfig> {
+    get_setting(conn, WORKER_POOL_CONFIG_KEY)
+}
+
+pub fn set_worker_pool_config(
+    conn: &Connection,
+    config: &WorkerPoolConfig,
+) -> rusqlite::Result<()> {
+    set_setting(conn, WORKER_POOL_CONFIG_KEY, config)
+}
+
+/// The saved restart policy, or the built-in default.
+pub fn get_worker_restart_policy(conn: &Connection) -> rusqlite::Result<WorkerRestartPolicy> {
+    get_setting(conn, WORKER_RESTART_POLICY_KEY)
+}
+
+pub fn set_worker_restart_policy(
+    conn: &Connection,
+    policy: &WorkerRestartPolicy,
+) -> rusqlite::Result<()> {
+    set_setting(conn, WORKER_RESTART_POLICY_KEY, policy)
+}
+
+fn get_setting<T: DeserializeOwned + Default>(conn: &Connection, key: &str) -> rusqlite::Result<T> {
+    let value_json: Option<String> = conn
+        .query_row(
+            "SELECT value_json FROM worker_settings WHERE key = ?1",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value_json
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+fn set_setting<T: Serialize>(conn: &Connection, key: &str, value: &T) -> rusqlite::Result<()> {
+    let value_json = serde_json::to_string(value).unwrap_or_else(|_| "{}".into());
+    conn.execute(
+        "INSERT INTO worker_settings (key, value_json, updated_at)
+         VALUES (?1, ?2, ?3)
+         ON CONFLICT(key) DO UPDATE SET
+             value_json = excluded.value_json,
+             updated_at = excluded.updated_at",
+        params![key, value_json, Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,87 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::ipc::protocol::Envelope;
+
+/// A command sent to the worker that has not been answered yet.
+#[derive(Debug, Clone)]
+pub struct PendingCommand {
+    pub event: String,
+    pub task_id: Option<String>,
+    deadline: Instant,
+}
+
+/// Tracks commands by `msg_id` until the worker answers with an Ack or Error
+/// whose `reply_to` names them, or until they time out.
+#[derive(Debug, Clone)]
+pub struct PendingCommands {
+    timeout: Duration,
+    pending: HashMap<String, PendingCommand>,
+}
+
+impl PendingCommands {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            pending: HashMap::new(),
+        }
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    pub fn track(&mut self, envelope: &Envelope) {
+        let task_id = envelope
+            .payload
+            .get("task_id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string);
+        self.pending.insert(
+            envelope.msg_id.clone(),
+            PendingCommand {
+                event: envelope.event.clone(),
+                task_id,
+                deadline: Instant::now() + self.timeout,
+            },
+        );
+    }
+
+    pub fn resolve(&mut self, msg_id: &str) -> Option<PendingCommand> {
+        self.pending.remove(msg_id)
+    }
+
+    /// Resolves the pending `event` command for `task_id`. A worker that
+    /// reports on a task has received its submit, acked or not.
+    pub fn resolve_task(&mut self, event: &str, task_id: &str) -> Option<PendingCommand> {
+        let msg_id = self
+            .pending
+            .iter()
+            .find(|(_, command)| {
+                command.event == event && command.task_id.as_deref() == Some(task_id)
+            })
+            .map(|(msg_id, _)| msg_id.clone())?;
+        self.pending.remove(&msg_id)
+    }
+
+    /// Removes and returns every command whose deadline has passed.
+    pub fn expire(&mut self) -> Vec<PendingCommand> {
+        let now = Instant::now();
+        let expired: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, command)| now >= command.deadline)
+            .map(|(msg_id, _)| msg_id.clone())
+            .collect();
+        expired
+            .iter()
+            .filter_map(|msg_id| self.pending.remove(msg_id))
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
@@ -1,52 +1,80 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
 
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::{
+    database::SharedConnection,
+    error::{AppError, AppResult},
     ipc::protocol::{ProgressPayload, TaskPayload, TaskResultPayload},
-    models::TaskStatus,
+    models::{
+        ConcurrencyLimits, ErrorClass, RetryPolicy, TaskGraphLink, TaskInfo, TaskPriority,
+        TaskStatus, TaskTimeouts,
+    },
+    repository::{retry_policies_repo, tasks_repo, worker_settings_repo},
 };
 
+/// How long the worker has to confirm a `task.cancel` before the task is
+/// failed without its confirmation.
+const CANCEL_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubmitTaskInput {
     pub task_type: String,
     pub project_id: String,
     #[serde(default = "default_task_config")]
     pub config: Value,
+    #[serde(default)]
+    pub priority: TaskPriority,
 }
 
-fn default_task_config() -> Value {
-    Value::Object(serde_json::Map::new())
+/// One node of a task graph. `key` names the node within its graph and
+/// `depends_on` lists the keys it waits for. Once released, the node's
+/// config gains an `upstream` object mapping each dependency key to that
+/// task's output.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskGraphNodeInput {
+    pub key: String,
+    pub task_type: String,
+    #[serde(default = "default_task_config")]
+    pub config: Value,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TaskInfo {
-    pub task_id: String,
-    pub task_type: String,
+pub struct SubmitTaskGraphInput {
     pub project_id: String,
-    pub status: TaskStatus,
-    pub progress: f64,
-    pub message: Option<String>,
-    pub output: Option<Value>,
-    pub error: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    #[serde(default)]
+    pub priority: TaskPriority,
+    pub nodes: Vec<TaskGraphNodeInput>,
+}
+
+fn default_task_config() -> Value {
+    Value::Object(serde_json::Map::new())
 }
 
 impl TaskInfo {
-    fn from_payload(payload: &TaskPayload) -> Self {
+    fn from_payload(payload: &TaskPayload, priority: TaskPriority, trace_id: &str) -> Self {
         let now = Utc::now().to_rfc3339();
         Self {
             task_id: payload.task_id.clone(),
             task_type: payload.task_type.clone(),
             project_id: payload.project_id.clone(),
             status: TaskStatus::Pending,
+            priority,
+            attempt: 1,
             progress: 0.0,
             message: Some("queued".into()),
+            config: payload.config.clone(),
             output: None,
             error: None,
+            graph: None,
+            trace_id: Some(trace_id.to_string()),
             created_at: now.clone(),
             updated_at: now,
         }
@@ -59,14 +87,72 @@ impl TaskInfo {
             task_type: "unknown".into(),
             project_id: String::new(),
             status: TaskStatus::Pending,
+            priority: TaskPriority::default(),
+            attempt: 1,
             progress: 0.0,
             message: None,
+            config: Value::Null,
             output: None,
             error: None,
+            graph: None,
+            trace_id: None,
             created_at: now.clone(),
             updated_at: now,
         }
     }
+
+    /// The trace id as printed in log lines.
+    pub fn trace_label(&self) -> &str {
+        self.trace_id.as_deref().unwrap_or("-")
+    }
+
+    pub fn to_payload(&self) -> TaskPayload {
+        TaskPayload {
+            task_id: self.task_id.clone(),
+            task_type: self.task_type.clone(),
+            project_id: self.project_id.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+/// What became of a task after the worker reported its result.
+pub enum TaskResolution {
+    Finished(TaskInfo),
+    /// The attempt failed but the task's retry policy allows another one,
+    /// to be resubmitted through `resume_retry` once `delay` has passed.
+    RetryScheduled {
+        task: TaskInfo,
+        delay: Duration,
+    },
+}
+
+/// A worker of the pool that has finished its handshake and may be given
+/// tasks.
+#[derive(Debug, Clone)]
+pub struct DispatchTarget {
+    pub worker: usize,
+    /// Task-type group the worker serves; `None` for the general pool.
+    pub group: Option<String>,
+    /// What the worker declared in its hello, if anything.
+    pub max_concurrency: Option<u32>,
+}
+
+/// When an in-flight task was last started and last heard from.
+#[derive(Debug, Clone, Copy)]
+struct TaskClock {
+    started_at: Instant,
+    last_activity_at: Instant,
+}
+
+impl TaskClock {
+    fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            started_at: now,
+            last_activity_at: now,
+        }
+    }
 }
 
 fn map_worker_status(status: &str, _error: Option<&str>) -> TaskStatus {
@@ -80,98 +166,1285 @@ fn map_worker_status(status: &str, _error: Option<&str>) -> TaskStatus {
     }
 }
 
-#[derive(Debug, Default)]
+/// Tracks live tasks in memory and writes every state change through to the
+/// `ai_tasks` table, which is the source of truth for task history.
+///
+/// Submissions wait in `queue` until `take_dispatchable` hands them to a
+/// worker within the configured `ConcurrencyLimits`; `in_flight` holds only
+/// tasks a worker has actually been given, mapped to that worker's index in
+/// the pool. Task types listed in `routes` only go to workers of their group.
+///
+/// Failed attempts are retried according to the task type's `RetryPolicy`.
+/// A retry either waits out its backoff in `retry_scheduled` or, when the
+/// worker itself was lost, waits in `awaiting_restart` for the next session.
+///
+/// Cancelling a running task is two-phase: it stays in flight as
+/// `Cancelling` until the worker confirms with `task.cancelled` or its
+/// deadline in `cancelling` passes.
+///
+/// Every in-flight task has a `TaskClock` checked against the configured
+/// `TaskTimeouts` by `expire_stuck_tasks`.
+///
+/// Graph nodes with unfinished dependencies wait in `blocked`. Tasks released
+/// or cancelled as a consequence of another task settling are collected in
+/// `graph_updates` so the manager can announce them.
+#[derive(Debug)]
 pub struct WorkerDispatcher {
+    db: SharedConnection,
     tasks: HashMap<String, TaskInfo>,
-    in_flight: HashSet<String>,
+    queue: BTreeMap<TaskPriority, VecDeque<String>>,
+    in_flight: HashMap<String, usize>,
+    retry_scheduled: HashSet<String>,
+    awaiting_restart: Vec<String>,
+    limits: ConcurrencyLimits,
+    routes: HashMap<String, String>,
+    cancelling: HashMap<String, Instant>,
+    clocks: HashMap<String, TaskClock>,
+    timeouts: TaskTimeouts,
+    graphs: HashMap<String, Vec<String>>,
+    blocked: HashSet<String>,
+    graph_updates: Vec<TaskInfo>,
 }
 
 impl WorkerDispatcher {
-    pub fn new() -> Self {
-        Self::default()
+    pub fn new(db: SharedConnection) -> Self {
+        let settings = db
+            .lock()
+            .map_err(|e| format!("database lock poisoned: {e}"))
+            .and_then(|conn| {
+                let limits = worker_settings_repo::get_concurrency_limits(&conn);
+                let timeouts = worker_settings_repo::get_task_timeouts(&conn);
+                limits
+                    .and_then(|limits| Ok((limits, timeouts?)))
+                    .map_err(|e| e.to_string())
+            });
+        let (limits, timeouts) = settings.unwrap_or_else(|err: String| {
+            tracing::error!(%err, "failed to load worker settings, using defaults");
+            Default::default()
+        });
+        Self {
+            db,
+            tasks: HashMap::new(),
+            queue: BTreeMap::new(),
+            in_flight: HashMap::new(),
+            retry_scheduled: HashSet::new(),
+            awaiting_restart: Vec::new(),
+            limits,
+            routes: HashMap::new(),
+            cancelling: HashMap::new(),
+            clocks: HashMap::new(),
+            timeouts,
+            graphs: HashMap::new(),
+            blocked: HashSet::new(),
+            graph_updates: Vec::new(),
+        }
     }
 
     pub fn contains_task(&self, task_id: &str) -> bool {
         self.tasks.contains_key(task_id)
     }
 
-    pub fn register_submission(&mut self, payload: &TaskPayload) -> TaskInfo {
-        let info = TaskInfo::from_payload(payload);
-        self.in_flight.insert(payload.task_id.clone());
+    pub fn trace_id(&self, task_id: &str) -> Option<&str> {
+        self.tasks.get(task_id)?.trace_id.as_deref()
+    }
+
+    /// Records a new submission and queues it for dispatch. A task whose
+    /// first write to `ai_tasks` fails is not queued, as it would never show
+    /// up in the task history.
+    pub fn enqueue(
+        &mut self,
+        payload: &TaskPayload,
+        priority: TaskPriority,
+        trace_id: &str,
+    ) -> AppResult<TaskInfo> {
+        let info = TaskInfo::from_payload(payload, priority, trace_id);
+        self.write(&info)?;
+        self.push_queue(&payload.task_id, priority);
         self.tasks.insert(payload.task_id.clone(), info.clone());
-        info
+        Ok(info)
+    }
+
+    /// Records every node of a task graph. Nodes without dependencies are
+    /// queued right away, the rest wait until their parents complete. All
+    /// nodes are written to `ai_tasks` in one transaction before any of them
+    /// is queued.
+    pub fn enqueue_graph(
+        &mut self,
+        graph_id: &str,
+        nodes: Vec<(TaskPayload, TaskGraphLink)>,
+        priority: TaskPriority,
+        trace_id: &str,
+    ) -> AppResult<Vec<TaskInfo>> {
+        let infos: Vec<TaskInfo> = nodes
+            .into_iter()
+            .map(|(payload, link)| {
+                let mut info = TaskInfo::from_payload(&payload, priority, trace_id);
+                if !link.depends_on.is_empty() {
+                    info.message = Some("waiting for dependencies".into());
+                }
+                info.graph = Some(link);
+                info
+            })
+            .collect();
+        {
+            let conn = self
+                .db
+                .lock()
+                .map_err(|e| AppError::lock_poisoned("database", e))?;
+            let tx = conn.unchecked_transaction()?;
+            for info in &infos {
+                tasks_repo::upsert(&tx, info)?;
+            }
+            tx.commit()?;
+        }
+
+        let mut members = Vec::with_capacity(infos.len());
+        for info in &infos {
+            let waits = info
+                .graph
+                .as_ref()
+                .is_some_and(|link| !link.depends_on.is_empty());
+            if waits {
+                self.blocked.insert(info.task_id.clone());
+            } else {
+                self.push_queue(&info.task_id, priority);
+            }
+            self.tasks.insert(info.task_id.clone(), info.clone());
+            members.push(info.task_id.clone());
+        }
+        self.graphs.insert(graph_id.to_string(), members);
+        Ok(infos)
+    }
+
+    /// Ids of the graph's tasks that a worker is currently running.
+    pub fn graph_in_flight(&self, graph_id: &str) -> Vec<String> {
+        self.graphs
+            .get(graph_id)
+            .map(|members| {
+                members
+                    .iter()
+                    .filter(|id| self.in_flight.contains_key(*id))
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Cancels every unfinished task of a graph, see `request_cancel`.
+    /// Returns `None` if the graph has no live tasks left.
+    pub fn cancel_graph(&mut self, graph_id: &str) -> Option<Vec<TaskInfo>> {
+        let members = self.graphs.get(graph_id)?.clone();
+        Some(
+            members
+                .iter()
+                .filter_map(|task_id| self.request_cancel(task_id))
+                .collect(),
+        )
+    }
+
+    /// Drains the graph nodes released or cancelled since the last call.
+    pub fn take_graph_updates(&mut self) -> Vec<TaskInfo> {
+        std::mem::take(&mut self.graph_updates)
+    }
+
+    /// Pops queued tasks in priority order for as long as the concurrency
+    /// limits allow, assigns each to the least busy of `targets` that serves
+    /// its task type and marks it in flight. The caller must send every
+    /// returned task to its worker. Tasks of a type that is at its own limit
+    /// or has no worker to go to are skipped, so they do not hold back other
+    /// types behind them.
+    pub fn take_dispatchable(&mut self, targets: &[DispatchTarget]) -> Vec<(usize, TaskInfo)> {
+        let mut running_by_type: HashMap<String, u32> = HashMap::new();
+        let mut load: HashMap<usize, usize> = HashMap::new();
+        for (task_id, worker) in &self.in_flight {
+            if let Some(task) = self.tasks.get(task_id) {
+                *running_by_type.entry(task.task_type.clone()).or_default() += 1;
+            }
+            *load.entry(*worker).or_default() += 1;
+        }
+
+        let mut free = (self.limits.max_concurrent as usize).saturating_sub(self.in_flight.len());
+        let mut picked = Vec::new();
+        for queue in self.queue.values_mut().rev() {
+            let mut idx = 0;
+            while free > 0 && idx < queue.len() {
+                let Some(task) = self.tasks.get(&queue[idx]) else {
+                    queue.remove(idx);
+                    continue;
+                };
+                let running = running_by_type.entry(task.task_type.clone()).or_default();
+                let type_limit = self.limits.per_task_type.get(&task.task_type);
+                if type_limit.is_some_and(|limit| *running >= *limit) {
+                    idx += 1;
+                    continue;
+                }
+                let group = self.routes.get(&task.task_type);
+                let target = targets
+                    .iter()
+                    .filter(|target| target.group.as_ref() == group)
+                    .map(|target| {
+                        let busy = load.get(&target.worker).copied().unwrap_or_default();
+                        (target, busy)
+                    })
+                    .filter(|(target, busy)| {
+                        target
+                            .max_concurrency
+                            .is_none_or(|cap| *busy < cap as usize)
+                    })
+                    .min_by_key(|(_, busy)| *busy);
+                let Some((target, _)) = target else {
+                    idx += 1;
+                    continue;
+                };
+                *running += 1;
+                *load.entry(target.worker).or_default() += 1;
+                free -= 1;
+                picked.extend(queue.remove(idx).map(|task_id| (target.worker, task_id)));
+            }
+        }
+
+        let now = Utc::now().to_rfc3339();
+        let mut dispatched = Vec::with_capacity(picked.len());
+        for (worker, task_id) in picked {
+            let Some(task) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            task.message = Some("dispatched".into());
+            task.updated_at = now.clone();
+            let info = task.clone();
+            self.clocks.insert(task_id.clone(), TaskClock::new());
+            self.in_flight.insert(task_id, worker);
+            self.persist(&info);
+            dispatched.push((worker, info));
+        }
+        dispatched
+    }
+
+    /// Changes the priority of a task that has not been dispatched yet. A
+    /// queued task moves to the back of its new priority level. Returns
+    /// `None` if the task is unknown, already running or finished.
+    pub fn reprioritize(&mut self, task_id: &str, priority: TaskPriority) -> Option<TaskInfo> {
+        if self.in_flight.contains_key(task_id) {
+            return None;
+        }
+        let task = self.tasks.get_mut(task_id)?;
+        if task.status != TaskStatus::Pending {
+            return None;
+        }
+        let previous = task.priority;
+        task.priority = priority;
+        task.updated_at = Utc::now().to_rfc3339();
+        let info = task.clone();
+        if previous != priority && self.remove_from_queue(task_id) {
+            self.push_queue(task_id, priority);
+        }
+        self.persist(&info);
+        Some(info)
+    }
+
+    pub fn concurrency_limits(&self) -> &ConcurrencyLimits {
+        &self.limits
+    }
+
+    pub fn set_concurrency_limits(&mut self, limits: ConcurrencyLimits) {
+        self.limits = limits;
+    }
+
+    /// Sends each listed task type only to workers of the group it maps to.
+    /// Unlisted types go to the general pool.
+    pub fn set_routes(&mut self, routes: HashMap<String, String>) {
+        self.routes = routes;
+    }
+
+    pub fn task_timeouts(&self) -> &TaskTimeouts {
+        &self.timeouts
+    }
+
+    pub fn set_task_timeouts(&mut self, timeouts: TaskTimeouts) {
+        self.timeouts = timeouts;
+    }
+
+    /// Fails every task in flight on `worker` that has run longer than its
+    /// task type allows or has gone quiet for too long. The failure carries
+    /// the `timeout` error code, so the retry policy decides what happens
+    /// next. The caller must tell the worker to cancel each returned task.
+    pub fn expire_stuck_tasks(&mut self, worker: usize) -> Vec<(String, TaskResolution)> {
+        let now = Instant::now();
+        let in_flight = &self.in_flight;
+        self.clocks
+            .retain(|task_id, _| in_flight.contains_key(task_id));
+
+        let expired: Vec<(String, String)> = self
+            .clocks
+            .iter()
+            .filter(|(task_id, _)| self.in_flight.get(*task_id) == Some(&worker))
+            .filter(|(task_id, _)| !self.cancelling.contains_key(*task_id))
+            .filter_map(|(task_id, clock)| {
+                let task = self.tasks.get(task_id)?;
+                let timeout = self.timeouts.for_task_type(&task.task_type);
+                let exceeded = |since: Instant, limit: Option<u64>| {
+                    limit.filter(|secs| now.duration_since(since) >= Duration::from_secs(*secs))
+                };
+                let error = if let Some(secs) = exceeded(clock.started_at, timeout.max_runtime_secs)
+                {
+                    format!("task timed out after {secs}s")
+                } else if let Some(secs) = exceeded(clock.last_activity_at, timeout.stall_secs) {
+                    format!("task made no progress for {secs}s")
+                } else {
+                    return None;
+                };
+                Some((task_id.clone(), error))
+            })
+            .collect();
+
+        expired
+            .into_iter()
+            .filter_map(|(task_id, error)| {
+                let resolution = self.apply_result(TaskResultPayload {
+                    task_id: task_id.clone(),
+                    status: TaskStatus::Failed.as_str().into(),
+                    output: None,
+                    error: Some(error),
+                    error_code: Some("timeout".into()),
+                })?;
+                Some((task_id, resolution))
+            })
+            .collect()
+    }
+
+    /// Fails every task on `worker` whose cancellation the worker has not
+    /// confirmed within the grace period.
+    pub fn expire_cancellations(&mut self, worker: usize) -> Vec<TaskInfo> {
+        let now = Instant::now();
+        let overdue: Vec<String> = self
+            .cancelling
+            .iter()
+            .filter(|(task_id, _)| self.in_flight.get(*task_id) == Some(&worker))
+            .filter(|(_, deadline)| now >= **deadline)
+            .map(|(task_id, _)| task_id.clone())
+            .collect();
+        overdue
+            .into_iter()
+            .filter_map(|task_id| {
+                let error = format!(
+                    "worker did not confirm cancellation within {}s",
+                    CANCEL_GRACE_PERIOD.as_secs()
+                );
+                self.fail_task(&task_id, error)
+            })
+            .collect()
     }
 
-    pub fn mark_started(&mut self, task_id: &str) -> Option<TaskInfo> {
+    pub fn mark_started(&mut self, worker: usize, task_id: &str) -> Option<TaskInfo> {
+        if self.is_settled_elsewhere(task_id) {
+            return None;
+        }
+        self.clocks.insert(task_id.to_string(), TaskClock::new());
         let task = self.tasks.get_mut(task_id)?;
-        task.status = TaskStatus::Running;
-        task.message = Some("started".into());
+        if task.status != TaskStatus::Cancelling {
+            task.status = TaskStatus::Running;
+            task.message = Some("started".into());
+        }
         task.updated_at = Utc::now().to_rfc3339();
-        self.in_flight.insert(task_id.to_string());
-        Some(task.clone())
+        let info = task.clone();
+        self.in_flight.insert(task_id.to_string(), worker);
+        self.persist(&info);
+        Some(info)
     }
 
-    pub fn apply_progress(&mut self, payload: ProgressPayload) -> Option<TaskInfo> {
+    pub fn apply_progress(&mut self, worker: usize, payload: ProgressPayload) -> Option<TaskInfo> {
+        if self.is_settled_elsewhere(&payload.task_id) {
+            return None;
+        }
+        self.clocks
+            .entry(payload.task_id.clone())
+            .or_insert_with(TaskClock::new)
+            .last_activity_at = Instant::now();
         let task = self
             .tasks
             .entry(payload.task_id.clone())
             .or_insert_with(|| TaskInfo::placeholder(&payload.task_id));
 
-        task.status = TaskStatus::Running;
+        if task.status != TaskStatus::Cancelling {
+            task.status = TaskStatus::Running;
+        }
         task.progress = payload.progress.clamp(0.0, 1.0);
         task.message = payload.message;
         task.updated_at = Utc::now().to_rfc3339();
-        self.in_flight.insert(payload.task_id);
-        Some(task.clone())
+        let info = task.clone();
+        self.in_flight.insert(payload.task_id, worker);
+        self.persist(&info);
+        Some(info)
     }
 
-    pub fn apply_result(&mut self, payload: TaskResultPayload) -> Option<TaskInfo> {
+    pub fn apply_result(&mut self, payload: TaskResultPayload) -> Option<TaskResolution> {
+        if self.is_settled_elsewhere(&payload.task_id) {
+            return None;
+        }
+        // A result that races a cancellation still counts if the task
+        // completed; any other outcome settles the cancellation.
+        let cancelling = self.cancelling.remove(&payload.task_id).is_some();
+        let status = match map_worker_status(&payload.status, payload.error.as_deref()) {
+            TaskStatus::Completed => TaskStatus::Completed,
+            _ if cancelling => TaskStatus::Cancelled,
+            status => status,
+        };
+        let class = ErrorClass::from_error_code(payload.error_code.as_deref());
+        self.in_flight.remove(&payload.task_id);
+
+        let task_type = self
+            .tasks
+            .get(&payload.task_id)
+            .map(|task| task.task_type.clone())
+            .unwrap_or_default();
+        let retry_policy = (status == TaskStatus::Failed).then(|| self.retry_policy(&task_type));
+
         let task = self
             .tasks
             .entry(payload.task_id.clone())
             .or_insert_with(|| TaskInfo::placeholder(&payload.task_id));
+        let delay = retry_policy.and_then(|policy| {
+            policy
+                .next_delay(task.attempt, class)
+                .map(|delay| (policy, delay))
+        });
 
-        task.status = map_worker_status(&payload.status, payload.error.as_deref());
-        if task.status == TaskStatus::Completed {
-            task.progress = 1.0;
-        }
         task.output = payload.output;
         task.error = payload.error;
+        task.updated_at = Utc::now().to_rfc3339();
+        let resolution = match delay {
+            Some((policy, delay)) => {
+                task.status = TaskStatus::Pending;
+                task.message = Some(format!(
+                    "retrying in {}s (attempt {} of {})",
+                    delay.as_secs().max(1),
+                    task.attempt + 1,
+                    policy.max_attempts
+                ));
+                self.retry_scheduled.insert(payload.task_id.clone());
+                TaskResolution::RetryScheduled {
+                    task: task.clone(),
+                    delay,
+                }
+            }
+            None => {
+                task.status = status;
+                if task.status == TaskStatus::Completed {
+                    task.progress = 1.0;
+                }
+                task.message = match (cancelling, &task.status) {
+                    (false, _) => None,
+                    (true, TaskStatus::Completed) => {
+                        Some("completed before the cancellation took effect".into())
+                    }
+                    (true, _) => Some("cancelled".into()),
+                };
+                TaskResolution::Finished(task.clone())
+            }
+        };
+        match &resolution {
+            TaskResolution::Finished(info) | TaskResolution::RetryScheduled { task: info, .. } => {
+                self.persist(info)
+            }
+        }
+        if let TaskResolution::Finished(_) = resolution {
+            self.settle_graph(&payload.task_id);
+        }
+        Some(resolution)
+    }
+
+    /// Queues the next attempt of a task whose retry backoff has elapsed.
+    /// Returns `None` if the task was cancelled or failed in the meantime.
+    pub fn resume_retry(&mut self, task_id: &str) -> Option<TaskInfo> {
+        if !self.retry_scheduled.remove(task_id) {
+            return None;
+        }
+        self.begin_next_attempt(task_id)
+    }
+
+    /// Called before a restart tears `worker` down: its tasks that may be
+    /// retried after losing their worker wait for the next session, the rest
+    /// fail. Returns every task whose state changed.
+    pub fn park_in_flight_for_restart(&mut self, worker: usize) -> Vec<TaskInfo> {
+        let task_ids = self.in_flight_on(worker);
+        for task_id in &task_ids {
+            self.in_flight.remove(task_id);
+        }
+        let now = Utc::now().to_rfc3339();
+        let mut changed: Vec<TaskInfo> = Vec::with_capacity(task_ids.len());
+        for task_id in task_ids {
+            // The worker going away settles a pending cancellation.
+            if self.cancelling.remove(&task_id).is_some() {
+                if let Some(task) = self.tasks.get_mut(&task_id) {
+                    task.status = TaskStatus::Cancelled;
+                    task.message = Some("cancelled".into());
+                    task.updated_at = now.clone();
+                    let info = task.clone();
+                    self.persist(&info);
+                    changed.push(info);
+                }
+                continue;
+            }
+            let Some(task_type) = self.tasks.get(&task_id).map(|t| t.task_type.clone()) else {
+                continue;
+            };
+            let policy = self.retry_policy(&task_type);
+            let Some(task) = self.tasks.get_mut(&task_id) else {
+                continue;
+            };
+            if policy
+                .next_delay(task.attempt, ErrorClass::WorkerLost)
+                .is_some()
+            {
+                task.status = TaskStatus::Pending;
+                task.message = Some(format!(
+                    "waiting for worker restart (attempt {} of {})",
+                    task.attempt + 1,
+                    policy.max_attempts
+                ));
+                self.awaiting_restart.push(task_id);
+            } else {
+                task.status = TaskStatus::Failed;
+                task.error = Some("worker restarted while task was in-flight".into());
+                task.message = None;
+            }
+            task.updated_at = now.clone();
+            let info = task.clone();
+            self.persist(&info);
+            changed.push(info);
+        }
+        for info in &changed {
+            if info.status.is_terminal() {
+                self.settle_graph(&info.task_id);
+            }
+        }
+        changed
+    }
+
+    /// Queues the next attempt of every task parked by a restart, to be
+    /// dispatched once a worker session is ready.
+    pub fn take_awaiting_restart(&mut self) -> Vec<TaskInfo> {
+        let task_ids = std::mem::take(&mut self.awaiting_restart);
+        task_ids
+            .iter()
+            .filter_map(|task_id| self.begin_next_attempt(task_id))
+            .collect()
+    }
+
+    pub fn fail_task(&mut self, task_id: &str, error: String) -> Option<TaskInfo> {
+        self.cancelling.remove(task_id);
+        self.remove_from_queue(task_id);
+        self.retry_scheduled.remove(task_id);
+        self.awaiting_restart.retain(|id| id != task_id);
+        self.in_flight.remove(task_id);
+        let task = self.tasks.get_mut(task_id)?;
+        task.status = TaskStatus::Failed;
+        task.error = Some(error);
         task.message = None;
         task.updated_at = Utc::now().to_rfc3339();
-        self.in_flight.remove(&payload.task_id);
-        Some(task.clone())
+        let info = task.clone();
+        self.persist(&info);
+        self.settle_graph(task_id);
+        Some(info)
+    }
+
+    /// Starts cancelling a task. A task the worker is running turns
+    /// `Cancelling` until `confirm_cancelled` or `expire_cancellations`
+    /// settles it; anything else is cancelled on the spot. Returns `None` if
+    /// the task is unknown or already finished.
+    pub fn request_cancel(&mut self, task_id: &str) -> Option<TaskInfo> {
+        let task = self.tasks.get_mut(task_id)?;
+        if task.status.is_terminal() {
+            return None;
+        }
+        if !self.in_flight.contains_key(task_id) {
+            return self.cancel_task(task_id);
+        }
+        if task.status != TaskStatus::Cancelling {
+            task.status = TaskStatus::Cancelling;
+            task.message = Some("cancelling".into());
+            task.updated_at = Utc::now().to_rfc3339();
+            self.cancelling
+                .insert(task_id.to_string(), Instant::now() + CANCEL_GRACE_PERIOD);
+        }
+        let info = task.clone();
+        self.persist(&info);
+        Some(info)
     }
 
-    pub fn cancel_task(&mut self, task_id: &str) -> Option<TaskInfo> {
+    /// Handles the worker's `task.cancelled` confirmation.
+    pub fn confirm_cancelled(&mut self, task_id: &str) -> Option<TaskInfo> {
+        if !self.in_flight.contains_key(task_id) {
+            return None;
+        }
+        self.cancel_task(task_id)
+    }
+
+    fn cancel_task(&mut self, task_id: &str) -> Option<TaskInfo> {
+        self.cancelling.remove(task_id);
         let task = self.tasks.get_mut(task_id)?;
         task.status = TaskStatus::Cancelled;
         task.message = Some("cancelled".into());
         task.updated_at = Utc::now().to_rfc3339();
+        let info = task.clone();
         self.in_flight.remove(task_id);
-        Some(task.clone())
+        self.remove_from_queue(task_id);
+        self.blocked.remove(task_id);
+        self.retry_scheduled.remove(task_id);
+        self.awaiting_restart.retain(|id| id != task_id);
+        self.persist(&info);
+        self.settle_graph(task_id);
+        Some(info)
     }
 
+    /// Fails every in-flight task, including those still queued, blocked on
+    /// dependencies or waiting to be retried. Used when the worker is stopped
+    /// on purpose or cannot come back.
     pub fn fail_all_in_flight(&mut self) {
-        let task_ids: Vec<String> = self.in_flight.drain().collect();
+        let cancelling: HashSet<String> = self.cancelling.drain().map(|(id, _)| id).collect();
+        let queued = std::mem::take(&mut self.queue).into_values().flatten();
+        let task_ids: Vec<String> = self
+            .in_flight
+            .drain()
+            .map(|(task_id, _)| task_id)
+            .chain(queued)
+            .chain(self.blocked.drain())
+            .chain(self.retry_scheduled.drain())
+            .chain(self.awaiting_restart.drain(..))
+            .collect();
+        self.graphs.clear();
+        self.graph_updates.clear();
         let now = Utc::now().to_rfc3339();
         for task_id in task_ids {
             if let Some(task) = self.tasks.get_mut(&task_id) {
-                task.status = TaskStatus::Failed;
-                task.error = Some("worker stopped while task was in-flight".into());
-                task.message = None;
+                if cancelling.contains(&task_id) {
+                    task.status = TaskStatus::Cancelled;
+                    task.message = Some("cancelled".into());
+                } else {
+                    task.status = TaskStatus::Failed;
+                    task.error = Some("worker stopped while task was in-flight".into());
+                    task.message = None;
+                }
                 task.updated_at = now.clone();
+                let info = task.clone();
+                self.persist(&info);
             }
         }
     }
 
+    pub fn has_scheduled_retry(&self, task_id: &str) -> bool {
+        self.retry_scheduled.contains(task_id)
+    }
+
+    pub fn is_in_flight(&self, task_id: &str) -> bool {
+        self.in_flight.contains_key(task_id)
+    }
+
+    /// Index of the worker running the task, if any.
+    pub fn worker_of(&self, task_id: &str) -> Option<usize> {
+        self.in_flight.get(task_id).copied()
+    }
+
+    /// Ids of the tasks `worker` is running.
+    pub fn in_flight_on(&self, worker: usize) -> Vec<String> {
+        self.in_flight
+            .iter()
+            .filter(|(_, assigned)| **assigned == worker)
+            .map(|(task_id, _)| task_id.clone())
+            .collect()
+    }
+
     pub fn in_flight_count(&self) -> usize {
         self.in_flight.len()
     }
 
-    pub fn list_tasks(&self) -> Vec<TaskInfo> {
-        let mut tasks: Vec<TaskInfo> = self.tasks.values().cloned().collect();
-        tasks.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-        tasks
+    pub fn queued_count(&self) -> usize {
+        self.queue.values().map(VecDeque::len).sum()
+    }
+
+    /// A known task that is not in flight was cancelled, timed out or is
+    /// waiting for a retry, so whatever the worker still reports for it is
+    /// stale. Unknown task ids are accepted and tracked as placeholders.
+    fn is_settled_elsewhere(&self, task_id: &str) -> bool {
+        self.tasks.contains_key(task_id) && !self.in_flight.contains_key(task_id)
+    }
+
+    fn begin_next_attempt(&mut self, task_id: &str) -> Option<TaskInfo> {
+        let task = self.tasks.get_mut(task_id)?;
+        if task.status != TaskStatus::Pending {
+            return None;
+        }
+        task.attempt += 1;
+        task.progress = 0.0;
+        task.output = None;
+        task.error = None;
+        task.message = Some(format!("queued (attempt {})", task.attempt));
+        task.updated_at = Utc::now().to_rfc3339();
+        let info = task.clone();
+        self.push_queue(task_id, info.priority);
+        self.persist(&info);
+        Some(info)
+    }
+
+    /// Releases or cancels the dependents of a graph node that has just
+    /// reached a final state.
+    fn settle_graph(&mut self, task_id: &str) {
+        let Some((link, status)) = self
+            .tasks
+            .get(task_id)
+            .and_then(|task| Some((task.graph.clone()?, task.status.clone())))
+        else {
+            return;
+        };
+        let Some(members) = self.graphs.get(&link.graph_id).cloned() else {
+            return;
+        };
+
+        if status == TaskStatus::Completed {
+            for member in &members {
+                self.release_if_ready(member, &members);
+            }
+        } else {
+            self.cancel_dependents(&link.node_key, &status, &members);
+        }
+
+        let live = members.iter().any(|id| {
+            self.tasks
+                .get(id)
+                .is_some_and(|task| !task.status.is_terminal())
+        });
+        if !live {
+            self.graphs.remove(&link.graph_id);
+        }
+    }
+
+    fn release_if_ready(&mut self, task_id: &str, members: &[String]) {
+        if !self.blocked.contains(task_id) {
+            return;
+        }
+        let Some(depends_on) = self
+            .tasks
+            .get(task_id)
+            .and_then(|task| task.graph.as_ref())
+            .map(|link| link.depends_on.clone())
+        else {
+            return;
+        };
+
+        let mut upstream = serde_json::Map::new();
+        for key in depends_on {
+            let parent = members
+                .iter()
+                .filter_map(|id| self.tasks.get(id))
+                .find(|task| task.graph.as_ref().is_some_and(|l| l.node_key == key));
+            match parent {
+                Some(parent) if parent.status == TaskStatus::Completed => {
+                    let output = parent.output.clone().unwrap_or(Value::Null);
+                    upstream.insert(key, output);
+                }
+                _ => return,
+            }
+        }
+
+        let Some(task) = self.tasks.get_mut(task_id) else {
+            return;
+        };
+        if let Value::Object(config) = &mut task.config {
+            config.insert("upstream".into(), Value::Object(upstream));
+        }
+        task.message = Some("queued".into());
+        task.updated_at = Utc::now().to_rfc3339();
+        let info = task.clone();
+        self.blocked.remove(task_id);
+        self.push_queue(task_id, info.priority);
+        self.persist(&info);
+        self.graph_updates.push(info);
+    }
+
+    /// Cancels every blocked node downstream of `node_key`.
+    fn cancel_dependents(&mut self, node_key: &str, status: &TaskStatus, members: &[String]) {
+        let now = Utc::now().to_rfc3339();
+        let mut frontier = vec![(node_key.to_string(), status.as_str())];
+        while let Some((key, outcome)) = frontier.pop() {
+            for member in members {
+                if !self.blocked.contains(member) {
+                    continue;
+                }
+                let Some(task) = self.tasks.get_mut(member) else {
+                    continue;
+                };
+                let Some(link) = task.graph.as_ref() else {
+                    continue;
+                };
+                if !link.depends_on.contains(&key) {
+                    continue;
+                }
+                let child_key = link.node_key.clone();
+                task.status = TaskStatus::Cancelled;
+                task.message = None;
+                task.error = Some(format!("upstream task {key} {outcome}"));
+                task.updated_at = now.clone();
+                let info = task.clone();
+                self.blocked.remove(member);
+                self.persist(&info);
+                self.graph_updates.push(info);
+                frontier.push((child_key, TaskStatus::Cancelled.as_str()));
+            }
+        }
+    }
+
+    fn push_queue(&mut self, task_id: &str, priority: TaskPriority) {
+        self.queue
+            .entry(priority)
+            .or_default()
+            .push_back(task_id.to_string());
+    }
+
+    fn remove_from_queue(&mut self, task_id: &str) -> bool {
+        for queue in self.queue.values_mut() {
+            if let Some(idx) = queue.iter().position(|id| id == task_id) {
+                queue.remove(idx);
+                return true;
+            }
+        }
+        false
+    }
+
+    fn retry_policy(&self, task_type: &str) -> RetryPolicy {
+        let result = self
+            .db
+            .lock()
+            .map_err(|e| format!("database lock poisoned: {e}"))
+            .and_then(|conn| {
+                retry_policies_repo::policy_for(&conn, task_type).map_err(|e| e.to_string())
+            });
+        result.unwrap_or_else(|err| {
+            tracing::error!(task_type, %err, "failed to load retry policy, using default");
+            RetryPolicy::default()
+        })
+    }
+
+    fn write(&self, task: &TaskInfo) -> AppResult<()> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| AppError::lock_poisoned("database", e))?;
+        tasks_repo::upsert(&conn, task)?;
+        Ok(())
+    }
+
+    fn persist(&self, task: &TaskInfo) {
+        // Placeholders for task ids the host never submitted have no project
+        // to attach to, so they only live in memory.
+        if task.project_id.is_empty() {
+            return;
+        }
+        if let Err(err) = self.write(task) {
+            tracing::error!(
+                task_id = %task.task_id,
+                trace_id = task.trace_label(),
+                %err,
+                "failed to persist task"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        database,
+        models::{CreateProjectInput, TaskRetryPolicy, TaskTimeout},
+        repository::{projects_repo, task_graphs_repo},
+    };
+
+    struct Fixture {
+        db: SharedConnection,
+        dispatcher: WorkerDispatcher,
+        project_id: String,
+    }
+
+    fn fixture() -> Fixture {
+        let db = database::open_in_memory();
+        let project_id = {
+            let conn = db.lock().unwrap();
+            let input = CreateProjectInput {
+                name: "dispatcher test".into(),
+                theme: None,
+                target_platform: None,
+            };
+            projects_repo::create(&conn, input).unwrap().id
+        };
+        let dispatcher = WorkerDispatcher::new(db.clone());
+        Fixture {
+            db,
+            dispatcher,
+            project_id,
+        }
+    }
+
+    fn general_worker(worker: usize) -> DispatchTarget {
+        DispatchTarget {
+            worker,
+            group: None,
+            max_concurrency: None,
+        }
+    }
+
+    impl Fixture {
+        fn payload(&self, task_id: &str, task_type: &str) -> TaskPayload {
+            TaskPayload {
+                task_id: task_id.into(),
+                task_type: task_type.into(),
+                project_id: self.project_id.clone(),
+                config: default_task_config(),
+            }
+        }
+
+        fn submit(&mut self, task_id: &str, task_type: &str, priority: TaskPriority) {
+            let payload = self.payload(task_id, task_type);
+            self.dispatcher
+                .enqueue(&payload, priority, "trace")
+                .unwrap();
+        }
+
+        /// Records a graph whose nodes are `(key, depends_on)` pairs; each
+        /// node's task id is its key.
+        fn submit_graph(&mut self, nodes: &[(&str, &[&str])]) -> String {
+            let graph_id = {
+                let conn = self.db.lock().unwrap();
+                task_graphs_repo::create(&conn, &self.project_id)
+                    .unwrap()
+                    .id
+            };
+            let nodes = nodes
+                .iter()
+                .map(|(key, depends_on)| {
+                    let link = TaskGraphLink {
+                        graph_id: graph_id.clone(),
+                        node_key: key.to_string(),
+                        depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+                    };
+                    (self.payload(key, "render"), link)
+                })
+                .collect();
+            self.dispatcher
+                .enqueue_graph(&graph_id, nodes, TaskPriority::Normal, "trace")
+                .unwrap();
+            graph_id
+        }
+
+        fn set_retry_policy(&self, task_type: &str, policy: RetryPolicy) {
+            let conn = self.db.lock().unwrap();
+            let entry = TaskRetryPolicy {
+                task_type: task_type.into(),
+                policy,
+            };
+            retry_policies_repo::upsert(&conn, &entry).unwrap();
+        }
+
+        /// Dispatches to a single general worker and returns the task ids.
+        fn dispatch(&mut self) -> Vec<String> {
+            self.dispatcher
+                .take_dispatchable(&[general_worker(0)])
+                .into_iter()
+                .map(|(_, task)| task.task_id)
+                .collect()
+        }
+
+        fn fail(&mut self, task_id: &str, error_code: Option<&str>) -> TaskResolution {
+            self.dispatcher
+                .apply_result(TaskResultPayload {
+                    task_id: task_id.into(),
+                    status: "failed".into(),
+                    output: None,
+                    error: Some("boom".into()),
+                    error_code: error_code.map(Into::into),
+                })
+                .expect("task was in flight")
+        }
+
+        fn status(&self, task_id: &str) -> TaskStatus {
+            self.dispatcher.tasks[task_id].status.clone()
+        }
+    }
+
+    fn retry_delay(resolution: TaskResolution) -> Option<Duration> {
+        match resolution {
+            TaskResolution::RetryScheduled { delay, .. } => Some(delay),
+            TaskResolution::Finished(_) => None,
+        }
+    }
+
+    #[test]
+    fn retry_backoff_doubles_up_to_the_cap() {
+        let mut f = fixture();
+        f.set_retry_policy(
+            "render",
+            RetryPolicy {
+                max_attempts: 5,
+                initial_backoff_ms: 1_000,
+                max_backoff_ms: 3_000,
+                retry_on: vec![ErrorClass::Network],
+            },
+        );
+        f.submit("t1", "render", TaskPriority::Normal);
+
+        let mut delays = Vec::new();
+        for _ in 0..4 {
+            assert_eq!(f.dispatch(), ["t1"]);
+            delays.push(retry_delay(f.fail("t1", Some("network"))));
+            assert!(f.dispatcher.resume_retry("t1").is_some());
+        }
+        assert_eq!(
+            delays,
+            [1_000, 2_000, 3_000, 3_000].map(|ms| Some(Duration::from_millis(ms)))
+        );
+
+        assert_eq!(f.dispatch(), ["t1"]);
+        assert_eq!(retry_delay(f.fail("t1", Some("network"))), None);
+        assert_eq!(f.status("t1"), TaskStatus::Failed);
+        assert_eq!(f.dispatcher.tasks["t1"].attempt, 5);
+    }
+
+    #[test]
+    fn only_listed_error_classes_are_retried() {
+        let mut f = fixture();
+        f.set_retry_policy(
+            "render",
+            RetryPolicy {
+                max_attempts: 3,
+                initial_backoff_ms: 10,
+                max_backoff_ms: 10,
+                retry_on: vec![ErrorClass::RateLimited],
+            },
+        );
+        f.submit("limited", "render", TaskPriority::Normal);
+        f.submit("broken", "render", TaskPriority::Normal);
+        f.submit("slow", "render", TaskPriority::Normal);
+        assert_eq!(f.dispatch(), ["limited", "broken"]);
+
+        assert!(retry_delay(f.fail("limited", Some("rate_limited"))).is_some());
+        assert!(f.dispatcher.has_scheduled_retry("limited"));
+        assert_eq!(f.status("limited"), TaskStatus::Pending);
+
+        assert_eq!(retry_delay(f.fail("broken", None)), None);
+        assert_eq!(f.status("broken"), TaskStatus::Failed);
+
+        assert_eq!(f.dispatch(), ["slow"]);
+        assert_eq!(retry_delay(f.fail("slow", Some("timeout"))), None);
+        assert_eq!(f.status("slow"), TaskStatus::Failed);
+        assert!(!f.dispatcher.has_scheduled_retry("slow"));
+    }
+
+    #[test]
+    fn higher_priorities_dispatch_first_in_submission_order() {
+        let mut f = fixture();
+        f.dispatcher.set_concurrency_limits(ConcurrencyLimits {
+            max_concurrent: 10,
+            ..ConcurrencyLimits::default()
+        });
+        f.submit("low", "render", TaskPriority::Low);
+        f.submit("normal-1", "render", TaskPriority::Normal);
+        f.submit("high", "render", TaskPriority::High);
+        f.submit("normal-2", "render", TaskPriority::Normal);
+
+        assert_eq!(f.dispatch(), ["high", "normal-1", "normal-2", "low"]);
+    }
+
+    #[test]
+    fn reprioritized_task_moves_to_the_back_of_its_new_level() {
+        let mut f = fixture();
+        f.dispatcher.set_concurrency_limits(ConcurrencyLimits {
+            max_concurrent: 10,
+            ..ConcurrencyLimits::default()
+        });
+        f.submit("high", "render", TaskPriority::High);
+        f.submit("normal", "render", TaskPriority::Normal);
+        f.submit("low", "render", TaskPriority::Low);
+
+        let info = f.dispatcher.reprioritize("low", TaskPriority::High);
+        assert_eq!(info.map(|task| task.priority), Some(TaskPriority::High));
+        assert_eq!(f.dispatch(), ["high", "low", "normal"]);
+
+        // Dispatched tasks keep their priority.
+        let low = TaskPriority::Low;
+        assert!(f.dispatcher.reprioritize("normal", low).is_none());
+        assert!(f.dispatcher.reprioritize("missing", low).is_none());
+    }
+
+    #[test]
+    fn task_type_at_its_limit_does_not_hold_back_others() {
+        let mut f = fixture();
+        f.dispatcher.set_concurrency_limits(ConcurrencyLimits {
+            max_concurrent: 3,
+            per_task_type: BTreeMap::from([("render".to_string(), 1)]),
+        });
+        f.submit("render-1", "render", TaskPriority::High);
+        f.submit("render-2", "render", TaskPriority::High);
+        f.submit("tts-1", "tts", TaskPriority::Normal);
+        f.submit("tts-2", "tts", TaskPriority::Normal);
+        f.submit("tts-3", "tts", TaskPriority::Normal);
+
+        assert_eq!(f.dispatch(), ["render-1", "tts-1", "tts-2"]);
+        assert!(f.dispatch().is_empty());
+
+        f.dispatcher.apply_result(TaskResultPayload {
+            task_id: "render-1".into(),
+            status: "completed".into(),
+            output: None,
+            error: None,
+            error_code: None,
+        });
+        assert_eq!(f.dispatch(), ["render-2"]);
+        assert_eq!(f.dispatcher.queued_count(), 1);
+    }
+
+    #[test]
+    fn failed_node_cancels_everything_downstream() {
+        let mut f = fixture();
+        let graph_id = f.submit_graph(&[
+            ("script", &[]),
+            ("voice", &["script"]),
+            ("video", &["voice"]),
+            ("subtitles", &["script"]),
+            ("cover", &[]),
+        ]);
+        assert_eq!(f.dispatch(), ["script", "cover"]);
+
+        assert_eq!(retry_delay(f.fail("script", None)), None);
+
+        let mut updates: Vec<(String, Option<String>)> = f
+            .dispatcher
+            .take_graph_updates()
+            .into_iter()
+            .map(|task| (task.task_id, task.error))
+            .collect();
+        updates.sort();
+        assert_eq!(
+            updates,
+            [
+                ("subtitles", "upstream task script failed"),
+                ("video", "upstream task voice cancelled"),
+                ("voice", "upstream task script failed"),
+            ]
+            .map(|(id, error)| (id.to_string(), Some(error.to_string())))
+        );
+        for task_id in ["voice", "video", "subtitles"] {
+            assert_eq!(f.status(task_id), TaskStatus::Cancelled);
+        }
+
+        // The independent branch keeps running.
+        assert_eq!(f.dispatcher.graph_in_flight(&graph_id), ["cover"]);
+        assert_eq!(f.dispatcher.queued_count(), 0);
+    }
+
+    #[test]
+    fn unconfirmed_cancellation_fails_after_the_grace_period() {
+        let mut f = fixture();
+        f.submit("t1", "render", TaskPriority::Normal);
+        f.submit("t2", "render", TaskPriority::Normal);
+        let targets = [general_worker(0), general_worker(1)];
+        assert_eq!(f.dispatcher.take_dispatchable(&targets).len(), 2);
+        let (on_first, on_second) = (f.dispatcher.worker_of("t1"), f.dispatcher.worker_of("t2"));
+        assert_eq!((on_first, on_second), (Some(0), Some(1)));
+
+        for task_id in ["t1", "t2"] {
+            let info = f.dispatcher.request_cancel(task_id).unwrap();
+            assert_eq!(info.status, TaskStatus::Cancelling);
+        }
+        assert!(f.dispatcher.expire_cancellations(0).is_empty());
+
+        // Let both grace periods run out.
+        for deadline in f.dispatcher.cancelling.values_mut() {
+            *deadline = Instant::now();
+        }
+        let expired = f.dispatcher.expire_cancellations(0);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].task_id, "t1");
+        assert_eq!(expired[0].status, TaskStatus::Failed);
+        assert!(!f.dispatcher.is_in_flight("t1"));
+        assert_eq!(f.status("t2"), TaskStatus::Cancelling);
+
+        // A confirmation arriving after the deadline is stale.
+        assert!(f.dispatcher.confirm_cancelled("t1").is_none());
+        assert_eq!(f.status("t1"), TaskStatus::Failed);
+    }
+
+    #[test]
+    fn confirmed_cancellation_within_the_grace_period_cancels() {
+        let mut f = fixture();
+        f.submit("t1", "render", TaskPriority::Normal);
+        assert_eq!(f.dispatch(), ["t1"]);
+        f.dispatcher.request_cancel("t1");
+
+        let info = f.dispatcher.confirm_cancelled("t1").unwrap();
+        assert_eq!(info.status, TaskStatus::Cancelled);
+        assert!(f.dispatcher.cancelling.is_empty());
+        assert!(f.dispatcher.expire_cancellations(0).is_empty());
+    }
+
+    #[test]
+    fn stuck_tasks_fail_with_the_timeout_code() {
+        let mut f = fixture();
+        f.set_retry_policy(
+            "tts",
+            RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            },
+        );
+        f.dispatcher.set_task_timeouts(TaskTimeouts {
+            default: TaskTimeout {
+                max_runtime_secs: None,
+                stall_secs: Some(60),
+            },
+            per_task_type: BTreeMap::from([(
+                "tts".to_string(),
+                TaskTimeout {
+                    max_runtime_secs: Some(600),
+                    stall_secs: None,
+                },
+            )]),
+        });
+        f.submit("render", "render", TaskPriority::Normal);
+        f.submit("tts", "tts", TaskPriority::Normal);
+        assert_eq!(f.dispatch(), ["render", "tts"]);
+        assert!(f.dispatcher.expire_stuck_tasks(0).is_empty());
+
+        // The render task has gone quiet and the tts task has run too long.
+        let ago = |secs| Instant::now() - Duration::from_secs(secs);
+        f.dispatcher.clocks.insert(
+            "render".into(),
+            TaskClock {
+                started_at: ago(120),
+                last_activity_at: ago(61),
+            },
+        );
+        f.dispatcher.clocks.insert(
+            "tts".into(),
+            TaskClock {
+                started_at: ago(601),
+                last_activity_at: ago(1),
+            },
+        );
+        let mut expired: HashMap<_, _> = f.dispatcher.expire_stuck_tasks(0).into_iter().collect();
+        assert_eq!(expired.len(), 2);
+        let (render, tts) = (expired.remove("render"), expired.remove("tts"));
+
+        // Timeouts are retried by the default policy.
+        assert_eq!(render.and_then(retry_delay), Some(Duration::from_secs(2)));
+        assert_eq!(
+            f.dispatcher.tasks["render"].error.as_deref(),
+            Some("task made no progress for 60s")
+        );
+        assert!(matches!(tts, Some(TaskResolution::Finished(_))));
+        assert_eq!(f.status("tts"), TaskStatus::Failed);
+        assert_eq!(
+            f.dispatcher.tasks["tts"].error.as_deref(),
+            Some("task timed out after 600s")
+        );
+        assert_eq!(f.dispatcher.in_flight_count(), 0);
     }
 }
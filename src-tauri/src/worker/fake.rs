@@ -0,0 +1,261 @@
+//! In-process stand-in for the `dy-worker` sidecar, scripted by tests.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ipc::protocol::{
+        deserialize_ndjson, Envelope, MessageKind, EVENT_TASK_COMPLETED, EVENT_TASK_FAILED,
+        EVENT_TASK_PROGRESS, EVENT_TASK_STARTED, EVENT_TASK_SUBMIT, EVENT_WORKER_HEARTBEAT,
+        EVENT_WORKER_HELLO, IPC_VERSION,
+    },
+    models::WorkerLaunchConfig,
+};
+
+use super::process::{LaunchedWorker, WorkerEvent, WorkerLauncher, WorkerProcess};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Hands out a `FakeWorker` per launch. Clones share the same launches.
+#[derive(Debug, Clone, Default)]
+pub struct FakeLauncher {
+    inner: Arc<Mutex<FakeLauncherState>>,
+}
+
+#[derive(Debug, Default)]
+struct FakeLauncherState {
+    workers: Vec<FakeWorker>,
+    configs: Vec<WorkerLaunchConfig>,
+    failures_left: usize,
+}
+
+impl FakeLauncher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes the next `count` launches fail as if the binary were missing.
+    pub fn fail_next_launches(&self, count: usize) {
+        self.inner.lock().unwrap().failures_left = count;
+    }
+
+    pub fn launches(&self) -> usize {
+        self.inner.lock().unwrap().workers.len()
+    }
+
+    /// The configuration each successful launch was given.
+    pub fn configs(&self) -> Vec<WorkerLaunchConfig> {
+        self.inner.lock().unwrap().configs.clone()
+    }
+
+    pub fn worker(&self, index: usize) -> FakeWorker {
+        self.inner.lock().unwrap().workers[index].clone()
+    }
+
+    /// Blocks until `count` workers have been launched and returns the last.
+    pub fn wait_for_launch(&self, count: usize, timeout: Duration) -> Option<FakeWorker> {
+        wait_until(timeout, || {
+            let state = self.inner.lock().unwrap();
+            (state.workers.len() >= count).then(|| state.workers[count - 1].clone())
+        })
+    }
+}
+
+impl WorkerLauncher for FakeLauncher {
+    fn launch(
+        &self,
+        _session_id: u64,
+        config: &WorkerLaunchConfig,
+    ) -> Result<LaunchedWorker, String> {
+        let mut state = self.inner.lock().unwrap();
+        if state.failures_left > 0 {
+            state.failures_left -= 1;
+            return Err("failed to spawn fake worker: no such file".into());
+        }
+
+        let (sender, events) = mpsc::channel();
+        let worker = FakeWorker {
+            events: sender.clone(),
+            stdin: Arc::new(Mutex::new(Vec::new())),
+            killed: Arc::new(AtomicBool::new(false)),
+        };
+        let process = FakeProcess {
+            events: sender,
+            stdin: Arc::clone(&worker.stdin),
+            killed: Arc::clone(&worker.killed),
+        };
+        state.workers.push(worker);
+        state.configs.push(config.clone());
+        Ok(LaunchedWorker {
+            events,
+            process: Box::new(process),
+            stderr_log: None,
+        })
+    }
+}
+
+/// One launched fake. Everything it sends arrives on the manager's event
+/// listener exactly as the sidecar's stdout would; saying nothing is how it
+/// hangs.
+#[derive(Debug, Clone)]
+pub struct FakeWorker {
+    events: Sender<WorkerEvent>,
+    stdin: Arc<Mutex<Vec<u8>>>,
+    killed: Arc<AtomicBool>,
+}
+
+impl FakeWorker {
+    pub fn send(&self, envelope: &Envelope) {
+        let line = envelope.to_ndjson_line().unwrap();
+        self.send_raw(line.as_bytes());
+    }
+
+    /// Writes bytes to stdout as-is, for partial or malformed frames.
+    pub fn send_raw(&self, bytes: &[u8]) {
+        let _ = self.events.send(WorkerEvent::Stdout(bytes.to_vec()));
+    }
+
+    pub fn stderr(&self, text: &str) {
+        let _ = self
+            .events
+            .send(WorkerEvent::Stderr(text.as_bytes().to_vec()));
+    }
+
+    pub fn hello(&self) {
+        self.hello_with(json!({ "ipc_versions": [IPC_VERSION] }));
+    }
+
+    pub fn hello_with(&self, capabilities: Value) {
+        self.event(EVENT_WORKER_HELLO, capabilities);
+    }
+
+    pub fn heartbeat(&self) {
+        self.event(EVENT_WORKER_HEARTBEAT, json!({}));
+    }
+
+    pub fn started(&self, task_id: &str) {
+        self.event(EVENT_TASK_STARTED, json!({ "task_id": task_id }));
+    }
+
+    pub fn progress(&self, task_id: &str, progress: f64) {
+        self.event(
+            EVENT_TASK_PROGRESS,
+            json!({ "task_id": task_id, "progress": progress, "message": null }),
+        );
+    }
+
+    pub fn complete(&self, task_id: &str) {
+        self.event(
+            EVENT_TASK_COMPLETED,
+            json!({ "task_id": task_id, "status": "completed", "output": null, "error": null }),
+        );
+    }
+
+    pub fn fail(&self, task_id: &str, error: &str, error_code: Option<&str>) {
+        self.event(
+            EVENT_TASK_FAILED,
+            json!({
+                "task_id": task_id,
+                "status": "failed",
+                "output": null,
+                "error": error,
+                "error_code": error_code,
+            }),
+        );
+    }
+
+    pub fn ack(&self, command: &Envelope) {
+        self.send(
+            &Envelope::new(MessageKind::Ack, command.event.clone(), json!({})).in_reply_to(command),
+        );
+    }
+
+    /// Exits as if the process died with `code`.
+    pub fn crash(&self, code: i32) {
+        let _ = self.events.send(WorkerEvent::Terminated {
+            code: Some(code),
+            signal: None,
+        });
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.killed.load(Ordering::SeqCst)
+    }
+
+    /// Every envelope the manager has written to this worker's stdin.
+    pub fn received(&self) -> Vec<Envelope> {
+        let stdin = self.stdin.lock().unwrap();
+        deserialize_ndjson(&String::from_utf8_lossy(&stdin)).unwrap()
+    }
+
+    /// Ids of the tasks the manager has submitted to this worker, in order.
+    pub fn submitted_tasks(&self) -> Vec<String> {
+        self.received()
+            .into_iter()
+            .filter(|envelope| envelope.event == EVENT_TASK_SUBMIT)
+            .filter_map(|envelope| envelope.payload["task_id"].as_str().map(str::to_string))
+            .collect()
+    }
+
+    /// Blocks until the manager has written an envelope for `event`.
+    pub fn wait_for(&self, event: &str, timeout: Duration) -> Option<Envelope> {
+        wait_until(timeout, || {
+            self.received()
+                .into_iter()
+                .find(|envelope| envelope.event == event)
+        })
+    }
+
+    fn event(&self, event: &str, payload: Value) {
+        self.send(&Envelope::new(MessageKind::Event, event, payload));
+    }
+}
+
+#[derive(Debug)]
+struct FakeProcess {
+    events: Sender<WorkerEvent>,
+    stdin: Arc<Mutex<Vec<u8>>>,
+    killed: Arc<AtomicBool>,
+}
+
+impl WorkerProcess for FakeProcess {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        if self.killed.load(Ordering::SeqCst) {
+            return Err("broken pipe".into());
+        }
+        self.stdin.lock().unwrap().extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn kill(self: Box<Self>) -> Result<(), String> {
+        self.killed.store(true, Ordering::SeqCst);
+        let _ = self.events.send(WorkerEvent::Terminated {
+            code: None,
+            signal: Some(9),
+        });
+        Ok(())
+    }
+}
+
+/// Polls `check` until it returns `Some` or `timeout` elapses.
+pub fn wait_until<T>(timeout: Duration, mut check: impl FnMut() -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(value) = check() {
+            return Some(value);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -31,6 +31,13 @@ impl HeartbeatMonitor {
         self.interval
     }
 
+    /// Applies new limits from the next check on, keeping the misses
+    /// counted so far.
+    pub fn configure(&mut self, interval: Duration, max_misses: u8) {
+        self.interval = interval;
+        self.max_misses = max_misses;
+    }
+
     pub fn reset(&mut self) {
         self.consecutive_misses = 0;
         self.last_heartbeat_at = None;
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::ipc::protocol::{Envelope, MessageKind};
+
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvelopeDirection {
+    ToWorker,
+    FromWorker,
+}
+
+/// An envelope as it crossed the IPC boundary, with every string in its
+/// payload replaced so prompts, paths and keys never leave the machine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvelopeRecord {
+    pub at: String,
+    /// Index of the pool worker on the other end.
+    pub worker: usize,
+    pub direction: EnvelopeDirection,
+    pub v: String,
+    pub kind: MessageKind,
+    pub event: String,
+    pub msg_id: String,
+    pub trace_id: String,
+    pub reply_to: Option<String>,
+    pub payload: Value,
+}
+
+/// Keeps the most recent envelopes exchanged with the workers for
+/// diagnostics, dropping the oldest once `capacity` is reached.
+#[derive(Debug, Clone)]
+pub struct EnvelopeJournal {
+    capacity: usize,
+    records: VecDeque<EnvelopeRecord>,
+}
+
+impl EnvelopeJournal {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            records: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, worker: usize, direction: EnvelopeDirection, envelope: &Envelope) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.records.len() == self.capacity {
+            self.records.pop_front();
+        }
+        self.records.push_back(EnvelopeRecord {
+            at: Utc::now().to_rfc3339(),
+            worker,
+            direction,
+            v: envelope.v.clone(),
+            kind: envelope.kind.clone(),
+            event: envelope.event.clone(),
+            msg_id: envelope.msg_id.clone(),
+            trace_id: envelope.trace_id.clone(),
+            reply_to: envelope.reply_to.clone(),
+            payload: redact(&envelope.payload),
+        });
+    }
+
+    pub fn snapshot(&self) -> Vec<EnvelopeRecord> {
+        self.records.iter().cloned().collect()
+    }
+}
+
+/// Keeps the payload's shape, numbers and booleans, and masks every string
+/// except the ids needed to follow a task through the log.
+fn redact(value: &Value) -> Value {
+    match value {
+        Value::String(_) => Value::String(REDACTED.into()),
+        Value::Array(items) => Value::Array(items.iter().map(redact).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, field)| {
+                    let keep = matches!(key.as_str(), "task_id" | "task_type" | "status")
+                        && field.is_string();
+                    let field = if keep { field.clone() } else { redact(field) };
+                    (key.clone(), field)
+                })
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
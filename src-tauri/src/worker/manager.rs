@@ -1,6 +1,8 @@
 use std::{
-    collections::VecDeque,
-    sync::{Arc, Mutex, MutexGuard},
+    collections::{HashMap, HashSet, VecDeque},
+    fs::File,
+    io::Write,
+    sync::{mpsc::Receiver, Arc, Mutex, MutexGuard},
     thread,
     time::{Duration, Instant},
 };
@@ -9,28 +11,41 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use tauri::{AppHandle, Emitter, Runtime};
-use tauri_plugin_shell::{
-    process::{CommandChild, CommandEvent},
-    ShellExt,
-};
 use uuid::Uuid;
 
-use crate::ipc::protocol::{
-    deserialize_ndjson, Envelope, MessageKind, ProgressPayload, TaskResultPayload,
-    EVENT_TASK_CANCEL, EVENT_TASK_COMPLETED, EVENT_TASK_FAILED, EVENT_TASK_PROGRESS,
-    EVENT_TASK_STARTED, EVENT_TASK_SUBMIT, EVENT_WORKER_HEARTBEAT, EVENT_WORKER_HELLO,
-    EVENT_WORKER_WELCOME, IPC_VERSION, TaskPayload,
+use crate::{
+    database::SharedConnection,
+    error::{AppError, AppResult, ErrorCode},
+    ipc::protocol::{
+        deserialize_ndjson, negotiate_ipc_version, Envelope, IpcVersion, MessageKind,
+        ProgressPayload, TaskPayload, TaskResultPayload, WorkerCapabilities, EVENT_TASK_CANCEL,
+        EVENT_TASK_CANCELLED, EVENT_TASK_COMPLETED, EVENT_TASK_FAILED, EVENT_TASK_PROGRESS,
+        EVENT_TASK_STARTED, EVENT_TASK_SUBMIT, EVENT_WORKER_HEARTBEAT, EVENT_WORKER_HELLO,
+        EVENT_WORKER_WELCOME, IPC_VERSION, MIN_IPC_VERSION,
+    },
+    media_store::MediaStore,
+    models::{
+        ConcurrencyLimits, TaskGraphLink, TaskGraphStatus, TaskInfo, TaskPriority, TaskStatus,
+        TaskTimeouts, WorkerLaunchConfig, WorkerPoolConfig, WorkerRestartPolicy,
+    },
+    repository::{projects_repo, task_graphs_repo, worker_settings_repo},
 };
 
 use super::{
-    dispatcher::{SubmitTaskInput, TaskInfo, WorkerDispatcher},
+    commands::{PendingCommand, PendingCommands},
+    dispatcher::{
+        DispatchTarget, SubmitTaskGraphInput, SubmitTaskInput, TaskGraphNodeInput, TaskResolution,
+        WorkerDispatcher,
+    },
     heartbeat::{HeartbeatCheck, HeartbeatMonitor},
+    journal::{EnvelopeDirection, EnvelopeJournal, EnvelopeRecord},
+    outputs::{register_task_outputs, AssetsRegisteredEvent},
+    process::{WorkerEvent, WorkerLauncher, WorkerProcess},
 };
 
-const RESTART_BACKOFF_SECONDS: [u64; 6] = [1, 2, 4, 8, 16, 30];
-const CIRCUIT_BREAKER_WINDOW: Duration = Duration::from_secs(10 * 60);
-const CIRCUIT_BREAKER_MAX_RESTARTS: usize = 5;
 const MAX_STDOUT_BUFFER_BYTES: usize = 1024 * 1024;
+const ENVELOPE_JOURNAL_CAPACITY: usize = 200;
+const RESTART_LOG_CAPACITY: usize = 50;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
@@ -41,163 +56,348 @@ pub enum WorkerState {
     Unhealthy,
     Stopped,
     CircuitBroken,
+    /// The circuit breaker was reset or has cooled down. The next start is a
+    /// single probe that closes the breaker once it says hello.
+    HalfOpen,
+    /// The handshake found no IPC version both sides speak.
+    Incompatible,
 }
 
+/// The worker pool at a glance. The top-level fields sum up the whole pool;
+/// `workers` has the detail for each process.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerStatus {
+    /// The most useful state any worker is in, so a pool with one ready
+    /// worker reads as ready while another one restarts.
+    pub state: WorkerState,
+    pub last_heartbeat: Option<String>,
+    pub restart_count_10m: usize,
+    pub in_flight_tasks: usize,
+    pub queued_tasks: usize,
+    /// What the workers declared in their last `worker.hello`.
+    pub capabilities: Option<WorkerCapabilities>,
+    /// Version agreed on in the current session's handshake.
+    pub ipc_version: Option<String>,
+    pub incompatible_reason: Option<String>,
+    pub workers: Vec<WorkerSlotStatus>,
+}
+
+/// One process of the worker pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerSlotStatus {
+    pub index: usize,
+    /// Task-type group the worker serves; `None` for the general pool.
+    pub group: Option<String>,
     pub state: WorkerState,
+    pub session_id: u64,
     pub last_heartbeat: Option<String>,
     pub restart_count_10m: usize,
     pub in_flight_tasks: usize,
+    pub ipc_version: Option<String>,
+    pub incompatible_reason: Option<String>,
+}
+
+/// A restart the manager decided on, kept for diagnostics.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RestartRecord {
+    pub at: String,
+    /// Index of the pool worker that was restarted.
+    pub worker: usize,
+    pub reason: String,
+    /// Whether this restart tripped the circuit breaker instead of running.
+    pub circuit_broken: bool,
+}
+
+/// Everything the worker layer contributes to a diagnostics bundle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerDiagnostics {
+    pub status: WorkerStatus,
+    pub restarts: Vec<RestartRecord>,
+    pub envelopes: Vec<EnvelopeRecord>,
 }
 
+/// How long the manager waits on the worker's protocol. Only tests change
+/// these; restarts and heartbeats follow the saved `WorkerRestartPolicy`.
+#[derive(Debug, Clone)]
+pub struct WorkerTimings {
+    pub command_ack_timeout: Duration,
+}
+
+impl Default for WorkerTimings {
+    fn default() -> Self {
+        Self {
+            command_ack_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Runs a pool of worker processes laid out by `WorkerPoolConfig`. Workers
+/// are addressed by their index in `workers`; each has its own session,
+/// heartbeat, restart backoff and circuit breaker, so one crash or hang only
+/// takes down the tasks of that worker.
 #[derive(Debug)]
 pub struct WorkerManager {
-    state: WorkerState,
-    child: Option<CommandChild>,
+    db: SharedConnection,
+    media_store: MediaStore,
+    launcher: Arc<dyn WorkerLauncher>,
+    launch_config: WorkerLaunchConfig,
+    pool_config: WorkerPoolConfig,
+    restart_policy: WorkerRestartPolicy,
+    workers: Vec<WorkerSlot>,
     dispatcher: WorkerDispatcher,
+    timings: WorkerTimings,
+    last_session_id: u64,
+    journal: EnvelopeJournal,
+    restart_log: VecDeque<RestartRecord>,
+}
+
+pub type SharedWorkerManager = Arc<Mutex<WorkerManager>>;
+
+/// One worker process and its current session. Session ids are unique
+/// across the pool, so threads left over from an earlier session, or from
+/// an earlier pool layout, find their session gone and exit.
+#[derive(Debug)]
+struct WorkerSlot {
+    group: Option<String>,
+    state: WorkerState,
+    child: Option<Box<dyn WorkerProcess>>,
+    session_id: u64,
     heartbeat: HeartbeatMonitor,
     restart_history: VecDeque<Instant>,
     backoff_step: usize,
     restart_scheduled: bool,
-    session_id: u64,
+    circuit_opened_at: Option<Instant>,
+    /// The current session is the probe of a half-open circuit breaker.
+    circuit_probe: bool,
+    capabilities: Option<WorkerCapabilities>,
+    ipc_version: Option<IpcVersion>,
+    incompatible_reason: Option<String>,
+    pending_commands: PendingCommands,
 }
 
-pub type SharedWorkerManager = Arc<Mutex<WorkerManager>>;
+impl WorkerSlot {
+    fn new(group: Option<String>, policy: &WorkerRestartPolicy, timings: &WorkerTimings) -> Self {
+        Self {
+            group,
+            state: WorkerState::Stopped,
+            child: None,
+            session_id: 0,
+            heartbeat: HeartbeatMonitor::new(
+                policy.heartbeat_interval(),
+                policy.heartbeat_max_misses,
+            ),
+            restart_history: VecDeque::new(),
+            backoff_step: 0,
+            restart_scheduled: false,
+            circuit_opened_at: None,
+            circuit_probe: false,
+            capabilities: None,
+            ipc_version: None,
+            incompatible_reason: None,
+            pending_commands: PendingCommands::new(timings.command_ack_timeout),
+        }
+    }
+
+    /// Running and through its handshake, so it can be given tasks.
+    fn is_live(&self) -> bool {
+        self.child.is_some() && matches!(self.state, WorkerState::Ready | WorkerState::Busy)
+    }
+
+    fn status(&self, index: usize, in_flight_tasks: usize) -> WorkerSlotStatus {
+        WorkerSlotStatus {
+            index,
+            group: self.group.clone(),
+            state: self.state.clone(),
+            session_id: self.session_id,
+            last_heartbeat: self
+                .heartbeat
+                .last_heartbeat()
+                .map(|ts| DateTime::<Utc>::from(ts).to_rfc3339()),
+            restart_count_10m: self.restart_history.len(),
+            in_flight_tasks,
+            ipc_version: self.ipc_version.map(|version| version.to_string()),
+            incompatible_reason: self.incompatible_reason.clone(),
+        }
+    }
+}
 
 enum RestartDecision {
-    CircuitBroken,
-    Delayed { delay: Duration, session_id: u64 },
+    CircuitBroken {
+        child: Option<Box<dyn WorkerProcess>>,
+        parked: Vec<TaskInfo>,
+        downstream: Vec<TaskInfo>,
+    },
+    Delayed {
+        delay: Duration,
+        session_id: u64,
+    },
 }
 
 impl WorkerManager {
-    pub fn new() -> SharedWorkerManager {
+    pub fn new(
+        db: SharedConnection,
+        media_store: MediaStore,
+        launcher: Arc<dyn WorkerLauncher>,
+        timings: WorkerTimings,
+    ) -> SharedWorkerManager {
+        let settings = db
+            .lock()
+            .map_err(|e| format!("database lock poisoned: {e}"))
+            .and_then(|conn| {
+                let load = || -> rusqlite::Result<_> {
+                    Ok((
+                        worker_settings_repo::get_worker_launch_config(&conn)?,
+                        worker_settings_repo::get_worker_pool_config(&conn)?,
+                        worker_settings_repo::get_worker_restart_policy(&conn)?,
+                    ))
+                };
+                load().map_err(|e| e.to_string())
+            });
+        let (launch_config, pool_config, restart_policy) =
+            settings.unwrap_or_else(|err: String| {
+                tracing::error!(%err, "failed to load worker launch settings, using defaults");
+                Default::default()
+            });
+        let mut dispatcher = WorkerDispatcher::new(Arc::clone(&db));
+        dispatcher.set_routes(pool_config.routes());
+        let workers = pool_config
+            .slots()
+            .into_iter()
+            .map(|group| WorkerSlot::new(group, &restart_policy, &timings))
+            .collect();
         Arc::new(Mutex::new(Self {
-            state: WorkerState::Stopped,
-            child: None,
-            dispatcher: WorkerDispatcher::new(),
-            heartbeat: HeartbeatMonitor::new(Duration::from_secs(2), 3),
-            restart_history: VecDeque::new(),
-            backoff_step: 0,
-            restart_scheduled: false,
-            session_id: 0,
+            db,
+            media_store,
+            launcher,
+            launch_config,
+            pool_config,
+            restart_policy,
+            workers,
+            dispatcher,
+            timings,
+            last_session_id: 0,
+            journal: EnvelopeJournal::new(ENVELOPE_JOURNAL_CAPACITY),
+            restart_log: VecDeque::new(),
         }))
     }
 
-    pub fn get_status(shared: &SharedWorkerManager) -> Result<WorkerStatus, String> {
+    pub fn get_status(shared: &SharedWorkerManager) -> AppResult<WorkerStatus> {
         let manager = lock(shared)?;
         Ok(manager.status_snapshot())
     }
 
-    pub fn list_tasks(shared: &SharedWorkerManager) -> Result<Vec<TaskInfo>, String> {
+    pub fn diagnostics(shared: &SharedWorkerManager) -> AppResult<WorkerDiagnostics> {
         let manager = lock(shared)?;
-        Ok(manager.dispatcher.list_tasks())
+        Ok(WorkerDiagnostics {
+            status: manager.status_snapshot(),
+            restarts: manager.restart_log.iter().cloned().collect(),
+            envelopes: manager.journal.snapshot(),
+        })
     }
 
+    /// Starts every worker of the pool that is not running yet. Fails only
+    /// if none of them could be launched. A worker whose circuit breaker is
+    /// half-open gets a single probe.
     pub fn start<R: Runtime + 'static>(
         shared: &SharedWorkerManager,
         app: &AppHandle<R>,
-    ) -> Result<(), String> {
-        let session_id = {
+    ) -> AppResult<()> {
+        let startable: Vec<usize> = {
             let mut m = lock(shared)?;
-            if m.state == WorkerState::CircuitBroken {
-                return Err("worker circuit breaker is open".into());
-            }
-            if m.child.is_some() {
-                return Ok(());
+            m.apply_pool_layout();
+            m.half_open_cooled_down_breakers();
+            if let Some(err) = m.unavailable_error() {
+                return Err(err);
             }
-            m.state = WorkerState::Starting;
-            m.session_id = m.session_id.wrapping_add(1);
-            m.restart_scheduled = false;
-            m.heartbeat.reset();
-            m.session_id
+            m.workers
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| {
+                    slot.child.is_none()
+                        && !matches!(
+                            slot.state,
+                            WorkerState::Starting
+                                | WorkerState::CircuitBroken
+                                | WorkerState::Incompatible
+                        )
+                })
+                .map(|(index, _)| index)
+                .collect()
         };
-        emit_status(app, shared);
 
-        let command = app
-            .shell()
-            .sidecar("dy-worker")
-            .map_err(|e| format!("failed to configure dy-worker sidecar: {e}"))?;
-
-        let (receiver, child) = match command.spawn() {
-            Ok(spawned) => spawned,
-            Err(e) => {
-                {
-                    let mut m = lock(shared)?;
-                    if m.session_id == session_id {
-                        m.state = WorkerState::Stopped;
-                    }
+        let mut launched = 0;
+        let mut first_error = None;
+        for worker in startable {
+            match Self::start_worker(shared, app, worker) {
+                Ok(()) => launched += 1,
+                Err(err) => {
+                    first_error.get_or_insert(err);
                 }
-                emit_status(app, shared);
-                return Err(format!("failed to spawn dy-worker sidecar: {e}"));
             }
-        };
-
-        {
-            let mut m = lock(shared)?;
-            if m.session_id != session_id {
-                let _ = child.kill();
-                return Ok(());
-            }
-            m.child = Some(child);
         }
-
-        spawn_event_listener(Arc::clone(shared), app.clone(), receiver, session_id);
-        spawn_heartbeat_monitor(Arc::clone(shared), app.clone(), session_id);
-
-        Ok(())
-    }
-
-    pub fn stop<R: Runtime>(shared: &SharedWorkerManager, app: &AppHandle<R>) -> Result<(), String> {
-        let child = {
-            let mut m = lock(shared)?;
-            m.session_id = m.session_id.wrapping_add(1);
-            m.restart_scheduled = false;
-            m.state = WorkerState::Stopped;
-            m.heartbeat.reset();
-            m.dispatcher.fail_all_in_flight();
-            m.child.take()
-        };
-
-        if let Some(child) = child {
-            let _ = child.kill();
+        match first_error {
+            Some(err) if launched == 0 => Err(err),
+            _ => Ok(()),
         }
+    }
 
-        emit_status(app, shared);
-        Ok(())
+    pub fn stop<R: Runtime>(shared: &SharedWorkerManager, app: &AppHandle<R>) -> AppResult<()> {
+        Self::shutdown(shared, app, false)
     }
 
+    /// Restarts the whole pool, picking up a changed launch configuration or
+    /// pool layout.
     pub fn restart<R: Runtime + 'static>(
         shared: &SharedWorkerManager,
         app: &AppHandle<R>,
         reason: &str,
-    ) -> Result<(), String> {
+    ) -> AppResult<()> {
         {
             let mut m = lock(shared)?;
-            m.restart_scheduled = false;
+            for slot in m.workers.iter_mut() {
+                slot.restart_scheduled = false;
+            }
         }
-        let _ = Self::stop(shared, app);
-        Self::start(shared, app).map_err(|e| format!("restart failed ({reason}): {e}"))
+        // In-flight tasks are parked instead of failed so that the new
+        // sessions can pick them up once they say hello.
+        let _ = Self::shutdown(shared, app, true);
+        Self::start(shared, app).map_err(|e| {
+            if let Ok(mut m) = lock(shared) {
+                m.dispatcher.fail_all_in_flight();
+            }
+            AppError {
+                message: format!("restart failed ({reason}): {}", e.message),
+                ..e
+            }
+        })
     }
 
     pub fn submit_task<R: Runtime + 'static>(
         shared: &SharedWorkerManager,
         app: &AppHandle<R>,
         input: SubmitTaskInput,
-    ) -> Result<String, String> {
+    ) -> AppResult<String> {
         if input.task_type.trim().is_empty() {
-            return Err("task_type cannot be empty".into());
+            return Err(AppError::validation(
+                "task_type",
+                "task_type cannot be empty",
+            ));
         }
         if input.project_id.trim().is_empty() {
-            return Err("project_id cannot be empty".into());
+            return Err(AppError::validation(
+                "project_id",
+                "project_id cannot be empty",
+            ));
         }
-
-        // Check if worker needs starting while holding the lock to avoid race
-        let need_start = {
+        {
             let m = lock(shared)?;
-            m.child.is_none()
-                && !matches!(m.state, WorkerState::Starting)
-        };
+            m.ensure_project_exists(&input.project_id)?;
+            m.ensure_task_type_supported("task_type", &input.task_type)?;
+        }
+
+        // Starting skips workers already starting or running, so two
+        // submissions that both see a stopped pool start it only once.
+        let need_start = lock(shared)?.needs_start();
         if need_start {
             Self::start(shared, app)?;
         }
@@ -208,125 +408,1118 @@ impl WorkerManager {
             project_id: input.project_id,
             config: input.config,
         };
+        let trace_id = Uuid::new_v4().to_string();
 
-        let envelope = Envelope::new(
-            MessageKind::Command,
-            EVENT_TASK_SUBMIT,
-            serde_json::to_value(&task_payload)
-                .map_err(|e| format!("failed to serialize task payload: {e}"))?,
-        );
+        let info = {
+            let mut m = lock(shared)?;
+            m.dispatcher
+                .enqueue(&task_payload, input.priority, &trace_id)?
+        };
 
+        let _ = app.emit("task:progress", info);
+        pump_queue(shared, app)?;
+        emit_status(app, shared);
+        Ok(task_payload.task_id)
+    }
+
+    pub fn submit_task_graph<R: Runtime + 'static>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        input: SubmitTaskGraphInput,
+    ) -> AppResult<TaskGraphStatus> {
+        if input.project_id.trim().is_empty() {
+            return Err(AppError::validation(
+                "project_id",
+                "project_id cannot be empty",
+            ));
+        }
+        validate_graph(&input.nodes)?;
         {
+            let m = lock(shared)?;
+            m.ensure_project_exists(&input.project_id)?;
+            for node in &input.nodes {
+                m.ensure_task_type_supported("nodes", &node.task_type)?;
+            }
+        }
+
+        if lock(shared)?.needs_start() {
+            Self::start(shared, app)?;
+        }
+
+        let graph = {
+            let m = lock(shared)?;
+            let conn =
+                m.db.lock()
+                    .map_err(|e| AppError::lock_poisoned("database", e))?;
+            task_graphs_repo::create(&conn, &input.project_id)?
+        };
+        // One trace covers the whole graph, as it is one user action.
+        let trace_id = Uuid::new_v4().to_string();
+        let nodes = input
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let payload = TaskPayload {
+                    task_id: Uuid::new_v4().to_string(),
+                    task_type: node.task_type,
+                    project_id: input.project_id.clone(),
+                    config: node.config,
+                };
+                let link = TaskGraphLink {
+                    graph_id: graph.id.clone(),
+                    node_key: node.key,
+                    depends_on: node.depends_on,
+                };
+                (payload, link)
+            })
+            .collect();
+
+        let infos = {
             let mut m = lock(shared)?;
-            write_envelope(&mut m, &envelope)?;
-            m.dispatcher.register_submission(&task_payload);
-            m.state = WorkerState::Busy;
+            m.dispatcher
+                .enqueue_graph(&graph.id, nodes, input.priority, &trace_id)?
+        };
+
+        for info in &infos {
+            let _ = app.emit("task:progress", info);
         }
+        pump_queue(shared, app)?;
+        emit_status(app, shared);
+        Ok(TaskGraphStatus::new(graph, infos))
+    }
 
+    /// Cancels every unfinished task of a graph. Returns `false` if the graph
+    /// is unknown to this session or has already settled.
+    pub fn cancel_task_graph<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        graph_id: &str,
+    ) -> AppResult<bool> {
+        if graph_id.trim().is_empty() {
+            return Err(AppError::validation("graph_id", "graph_id cannot be empty"));
+        }
+
+        let cancelled = {
+            let mut m = lock(shared)?;
+            for task_id in m.dispatcher.graph_in_flight(graph_id) {
+                let Some(worker) = m.dispatcher.worker_of(&task_id) else {
+                    continue;
+                };
+                let envelope = cancel_envelope(&task_id, m.dispatcher.trace_id(&task_id));
+                write_envelope(&mut m, worker, &envelope)?;
+            }
+            let cancelled = m.dispatcher.cancel_graph(graph_id);
+            m.settle_idle_workers();
+            cancelled.map(|mut cancelled| {
+                cancelled.extend(m.dispatcher.take_graph_updates());
+                cancelled
+            })
+        };
+        let Some(cancelled) = cancelled else {
+            return Ok(false);
+        };
+
+        emit_task_updates(app, cancelled);
+        pump_queue(shared, app)?;
         emit_status(app, shared);
-        Ok(task_payload.task_id)
+        Ok(true)
+    }
+
+    pub fn reprioritize_task<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        task_id: &str,
+        priority: TaskPriority,
+    ) -> AppResult<TaskInfo> {
+        if task_id.trim().is_empty() {
+            return Err(AppError::validation("task_id", "task_id cannot be empty"));
+        }
+
+        let info = {
+            let mut m = lock(shared)?;
+            if !m.dispatcher.contains_task(task_id) {
+                return Err(AppError::not_found("task", task_id));
+            }
+            m.dispatcher
+                .reprioritize(task_id, priority)
+                .ok_or_else(|| {
+                    AppError::validation("task_id", "only queued tasks can be reprioritized")
+                })?
+        };
+
+        let _ = app.emit("task:progress", info.clone());
+        pump_queue(shared, app)?;
+        Ok(info)
+    }
+
+    pub fn get_task_timeouts(shared: &SharedWorkerManager) -> AppResult<TaskTimeouts> {
+        let m = lock(shared)?;
+        Ok(m.dispatcher.task_timeouts().clone())
+    }
+
+    /// Saves new timeouts. They apply to running tasks from the next
+    /// watchdog tick on.
+    pub fn set_task_timeouts(
+        shared: &SharedWorkerManager,
+        timeouts: TaskTimeouts,
+    ) -> AppResult<()> {
+        let mut m = lock(shared)?;
+        {
+            let conn =
+                m.db.lock()
+                    .map_err(|e| AppError::lock_poisoned("database", e))?;
+            worker_settings_repo::set_task_timeouts(&conn, &timeouts)?;
+        }
+        m.dispatcher.set_task_timeouts(timeouts);
+        Ok(())
+    }
+
+    pub fn get_launch_config(shared: &SharedWorkerManager) -> AppResult<WorkerLaunchConfig> {
+        let m = lock(shared)?;
+        Ok(m.launch_config.clone())
+    }
+
+    /// Saves how the worker is started. A running worker is left alone; the
+    /// new configuration takes effect on the next start or restart.
+    pub fn set_launch_config(
+        shared: &SharedWorkerManager,
+        config: WorkerLaunchConfig,
+    ) -> AppResult<()> {
+        let mut m = lock(shared)?;
+        {
+            let conn =
+                m.db.lock()
+                    .map_err(|e| AppError::lock_poisoned("database", e))?;
+            worker_settings_repo::set_worker_launch_config(&conn, &config)?;
+        }
+        m.launch_config = config;
+        Ok(())
+    }
+
+    pub fn get_pool_config(shared: &SharedWorkerManager) -> AppResult<WorkerPoolConfig> {
+        let m = lock(shared)?;
+        Ok(m.pool_config.clone())
+    }
+
+    /// Saves the pool layout. Running workers are left alone; the new layout
+    /// takes effect once the whole pool has been stopped and started again.
+    pub fn set_pool_config(
+        shared: &SharedWorkerManager,
+        config: WorkerPoolConfig,
+    ) -> AppResult<()> {
+        let mut m = lock(shared)?;
+        {
+            let conn =
+                m.db.lock()
+                    .map_err(|e| AppError::lock_poisoned("database", e))?;
+            worker_settings_repo::set_worker_pool_config(&conn, &config)?;
+        }
+        m.pool_config = config;
+        Ok(())
+    }
+
+    pub fn get_restart_policy(shared: &SharedWorkerManager) -> AppResult<WorkerRestartPolicy> {
+        let m = lock(shared)?;
+        Ok(m.restart_policy.clone())
+    }
+
+    /// Saves the restart policy and applies it right away: the next restart
+    /// uses the new backoff and breaker limits, and running workers are held
+    /// to the new heartbeat interval from their next check on.
+    pub fn set_restart_policy(
+        shared: &SharedWorkerManager,
+        policy: WorkerRestartPolicy,
+    ) -> AppResult<()> {
+        let mut m = lock(shared)?;
+        {
+            let conn =
+                m.db.lock()
+                    .map_err(|e| AppError::lock_poisoned("database", e))?;
+            worker_settings_repo::set_worker_restart_policy(&conn, &policy)?;
+        }
+        for slot in m.workers.iter_mut() {
+            slot.heartbeat
+                .configure(policy.heartbeat_interval(), policy.heartbeat_max_misses);
+        }
+        m.restart_policy = policy;
+        Ok(())
+    }
+
+    /// Half-opens every open circuit breaker, so that the next start sends
+    /// one probe per broken worker. Returns `false` if no breaker was open.
+    pub fn reset_circuit<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+    ) -> AppResult<bool> {
+        let reset = {
+            let mut m = lock(shared)?;
+            let broken: Vec<usize> = m
+                .workers
+                .iter()
+                .enumerate()
+                .filter(|(_, slot)| slot.state == WorkerState::CircuitBroken)
+                .map(|(index, _)| index)
+                .collect();
+            for &worker in &broken {
+                m.half_open_circuit(worker);
+            }
+            !broken.is_empty()
+        };
+
+        if reset {
+            emit_status(app, shared);
+        }
+        Ok(reset)
     }
 
+    pub fn get_concurrency_limits(shared: &SharedWorkerManager) -> AppResult<ConcurrencyLimits> {
+        let m = lock(shared)?;
+        Ok(m.dispatcher.concurrency_limits().clone())
+    }
+
+    /// Saves new limits and applies them right away. Lowering a limit never
+    /// interrupts running tasks; it only holds back further dispatches.
+    pub fn set_concurrency_limits<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        limits: ConcurrencyLimits,
+    ) -> AppResult<()> {
+        {
+            let mut m = lock(shared)?;
+            {
+                let conn =
+                    m.db.lock()
+                        .map_err(|e| AppError::lock_poisoned("database", e))?;
+                worker_settings_repo::set_concurrency_limits(&conn, &limits)?;
+            }
+            m.dispatcher.set_concurrency_limits(limits);
+        }
+
+        pump_queue(shared, app)
+    }
+
+    /// Cancels a task. A task the worker is running stays `cancelling`
+    /// until the worker confirms; see `WorkerDispatcher::request_cancel`.
     pub fn cancel_task<R: Runtime>(
         shared: &SharedWorkerManager,
         app: &AppHandle<R>,
         task_id: &str,
-    ) -> Result<bool, String> {
+    ) -> AppResult<bool> {
         if task_id.trim().is_empty() {
-            return Err("task_id cannot be empty".into());
+            return Err(AppError::validation("task_id", "task_id cannot be empty"));
         }
 
-        let cancelled = {
+        let updates = {
             let mut m = lock(shared)?;
             if !m.dispatcher.contains_task(task_id) {
                 return Ok(false);
             }
 
-            let envelope = Envelope::new(
-                MessageKind::Command,
-                EVENT_TASK_CANCEL,
-                json!({ "task_id": task_id }),
-            );
-            write_envelope(&mut m, &envelope)?;
-            m.dispatcher.cancel_task(task_id);
-
-            if m.dispatcher.in_flight_count() == 0 {
-                m.state = WorkerState::Ready;
+            // Queued tasks and tasks waiting for a retry were never handed
+            // to a worker, so there is nothing to tell one.
+            if let Some(worker) = m.dispatcher.worker_of(task_id) {
+                let envelope = cancel_envelope(task_id, m.dispatcher.trace_id(task_id));
+                write_envelope(&mut m, worker, &envelope)?;
             }
-            true
+            let Some(cancelled) = m.dispatcher.request_cancel(task_id) else {
+                return Ok(false);
+            };
+
+            m.settle_idle_workers();
+            let mut updates = vec![cancelled];
+            updates.extend(m.dispatcher.take_graph_updates());
+            updates
         };
 
+        emit_task_updates(app, updates);
+        pump_queue(shared, app)?;
         emit_status(app, shared);
-        Ok(cancelled)
+        Ok(true)
     }
 }
 
 // --- Internal helpers ---
 
 impl WorkerManager {
+    /// Starts one worker of the pool unless it is already starting or
+    /// running.
+    fn start_worker<R: Runtime + 'static>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        worker: usize,
+    ) -> AppResult<()> {
+        let (session_id, launcher, config) = {
+            let mut m = lock(shared)?;
+            let slot = m.workers.get(worker);
+            if slot.is_none_or(|slot| slot.child.is_some() || slot.state == WorkerState::Starting) {
+                return Ok(());
+            }
+            (
+                m.begin_session(worker),
+                Arc::clone(&m.launcher),
+                m.launch_config.clone(),
+            )
+        };
+        emit_status(app, shared);
+
+        let launched = match launcher.launch(session_id, &config) {
+            Ok(launched) => launched,
+            Err(e) => {
+                tracing::error!(worker, session_id, error = %e, "failed to launch worker");
+                {
+                    let mut m = lock(shared)?;
+                    if m.is_current_session(worker, session_id) {
+                        m.end_failed_launch(worker);
+                    }
+                }
+                emit_status(app, shared);
+                return Err(AppError::worker(e));
+            }
+        };
+
+        let stale = {
+            let mut m = lock(shared)?;
+            if m.is_current_session(worker, session_id) {
+                m.workers[worker].child = Some(launched.process);
+                None
+            } else {
+                Some(launched.process)
+            }
+        };
+        if let Some(process) = stale {
+            let _ = process.kill();
+            return Ok(());
+        }
+        tracing::info!(worker, session_id, program = ?config.program, "worker started");
+
+        spawn_event_listener(
+            Arc::clone(shared),
+            app.clone(),
+            launched.events,
+            worker,
+            session_id,
+            launched.stderr_log,
+        );
+        spawn_heartbeat_monitor(Arc::clone(shared), app.clone(), worker, session_id);
+
+        Ok(())
+    }
+
+    /// Replaces one worker while the rest of the pool keeps running.
+    fn restart_worker<R: Runtime + 'static>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        worker: usize,
+        reason: &str,
+    ) -> AppResult<()> {
+        let _ = Self::shutdown_worker(shared, app, worker);
+        Self::start_worker(shared, app, worker).map_err(|e| {
+            if let Ok(mut m) = lock(shared) {
+                if !m.has_active_worker() {
+                    m.dispatcher.fail_all_in_flight();
+                }
+            }
+            AppError {
+                message: format!("restart failed ({reason}): {}", e.message),
+                ..e
+            }
+        })
+    }
+
+    fn shutdown<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        retry_in_flight: bool,
+    ) -> AppResult<()> {
+        let (children, parked, downstream) = {
+            let mut m = lock(shared)?;
+            let mut children = Vec::new();
+            let mut parked = Vec::new();
+            for worker in 0..m.workers.len() {
+                tracing::info!(
+                    worker,
+                    session_id = m.workers[worker].session_id,
+                    retry_in_flight,
+                    "stopping worker"
+                );
+                children.extend(m.stop_slot(worker));
+                if retry_in_flight {
+                    parked.extend(m.dispatcher.park_in_flight_for_restart(worker));
+                }
+            }
+            if !retry_in_flight {
+                m.dispatcher.fail_all_in_flight();
+            }
+            (children, parked, m.dispatcher.take_graph_updates())
+        };
+
+        for child in children {
+            let _ = child.kill();
+        }
+
+        emit_status(app, shared);
+        emit_parked(app, parked);
+        emit_task_updates(app, downstream);
+        Ok(())
+    }
+
+    /// Stops one worker ahead of a restart. Its in-flight tasks move to the
+    /// rest of the pool if another worker is ready, or wait for the next
+    /// session otherwise.
+    fn shutdown_worker<R: Runtime>(
+        shared: &SharedWorkerManager,
+        app: &AppHandle<R>,
+        worker: usize,
+    ) -> AppResult<()> {
+        let (child, parked, downstream) = {
+            let mut m = lock(shared)?;
+            let Some(slot) = m.workers.get(worker) else {
+                return Ok(());
+            };
+            tracing::info!(
+                worker,
+                session_id = slot.session_id,
+                retry_in_flight = true,
+                "stopping worker"
+            );
+            let child = m.stop_slot(worker);
+            let mut parked = m.dispatcher.park_in_flight_for_restart(worker);
+            parked.extend(m.reassign_parked());
+            (child, parked, m.dispatcher.take_graph_updates())
+        };
+
+        if let Some(child) = child {
+            let _ = child.kill();
+        }
+
+        emit_status(app, shared);
+        emit_parked(app, parked);
+        emit_task_updates(app, downstream);
+        pump_queue(shared, app)
+    }
+
     fn status_snapshot(&self) -> WorkerStatus {
+        let workers: Vec<WorkerSlotStatus> = self
+            .workers
+            .iter()
+            .enumerate()
+            .map(|(index, slot)| slot.status(index, self.dispatcher.in_flight_on(index).len()))
+            .collect();
         let last_heartbeat = self
-            .heartbeat
-            .last_heartbeat()
+            .workers
+            .iter()
+            .filter_map(|slot| slot.heartbeat.last_heartbeat())
+            .max()
             .map(|ts| DateTime::<Utc>::from(ts).to_rfc3339());
 
         WorkerStatus {
-            state: self.state.clone(),
+            state: self.pool_state(),
             last_heartbeat,
-            restart_count_10m: self.restart_history.len(),
+            restart_count_10m: workers.iter().map(|worker| worker.restart_count_10m).sum(),
             in_flight_tasks: self.dispatcher.in_flight_count(),
+            queued_tasks: self.dispatcher.queued_count(),
+            capabilities: self.capabilities().cloned(),
+            ipc_version: workers.iter().find_map(|worker| worker.ipc_version.clone()),
+            incompatible_reason: workers
+                .iter()
+                .find_map(|worker| worker.incompatible_reason.clone()),
+            workers,
+        }
+    }
+
+    /// Busy beats ready beats starting, and so on down to stopped.
+    fn pool_state(&self) -> WorkerState {
+        [
+            WorkerState::Busy,
+            WorkerState::Ready,
+            WorkerState::Starting,
+            WorkerState::Unhealthy,
+            WorkerState::HalfOpen,
+            WorkerState::CircuitBroken,
+            WorkerState::Incompatible,
+        ]
+        .into_iter()
+        .find(|state| self.workers.iter().any(|slot| slot.state == *state))
+        .unwrap_or(WorkerState::Stopped)
+    }
+
+    /// What the workers declared in their hello. Every worker runs the same
+    /// program, so the first answer speaks for all of them.
+    fn capabilities(&self) -> Option<&WorkerCapabilities> {
+        self.workers
+            .iter()
+            .find_map(|slot| slot.capabilities.as_ref())
+    }
+
+    /// Nothing is running or starting, so a submission has to start the pool.
+    fn needs_start(&self) -> bool {
+        self.workers
+            .iter()
+            .all(|slot| slot.child.is_none() && slot.state != WorkerState::Starting)
+    }
+
+    /// Whether any worker is running or on its way back up.
+    fn has_active_worker(&self) -> bool {
+        self.workers.iter().any(|slot| {
+            matches!(
+                slot.state,
+                WorkerState::Starting
+                    | WorkerState::Ready
+                    | WorkerState::Busy
+                    | WorkerState::Unhealthy
+            )
+        })
+    }
+
+    fn is_current_session(&self, worker: usize, session_id: u64) -> bool {
+        self.workers
+            .get(worker)
+            .is_some_and(|slot| slot.session_id == session_id)
+    }
+
+    /// Why nothing can be started, once every worker is down for good.
+    fn unavailable_error(&self) -> Option<AppError> {
+        let down_for_good = |slot: &WorkerSlot| {
+            matches!(
+                slot.state,
+                WorkerState::CircuitBroken | WorkerState::Incompatible
+            )
+        };
+        if self.workers.is_empty() || !self.workers.iter().all(down_for_good) {
+            return None;
+        }
+        let error = match self
+            .workers
+            .iter()
+            .find_map(|slot| slot.incompatible_reason.as_deref())
+        {
+            Some(reason) => AppError::new(
+                ErrorCode::WorkerUnavailable,
+                format!("worker is incompatible with this host: {reason}"),
+            ),
+            None => AppError::new(
+                ErrorCode::WorkerUnavailable,
+                "worker circuit breaker is open",
+            ),
+        };
+        Some(error)
+    }
+
+    /// Rebuilds the pool from the saved layout once every worker is stopped.
+    /// Workers keep their restart history while the layout stays the same.
+    fn apply_pool_layout(&mut self) {
+        let layout = self.pool_config.slots();
+        let stopped = self
+            .workers
+            .iter()
+            .all(|slot| slot.child.is_none() && slot.state == WorkerState::Stopped);
+        let unchanged = self
+            .workers
+            .iter()
+            .map(|slot| &slot.group)
+            .eq(layout.iter());
+        if !stopped || unchanged {
+            return;
         }
+        tracing::info!(workers = layout.len(), "applying worker pool layout");
+        self.workers = layout
+            .into_iter()
+            .map(|group| WorkerSlot::new(group, &self.restart_policy, &self.timings))
+            .collect();
+        self.dispatcher.set_routes(self.pool_config.routes());
     }
 
-    fn next_backoff_delay(&mut self) -> Duration {
-        let idx = self.backoff_step.min(RESTART_BACKOFF_SECONDS.len() - 1);
-        self.backoff_step = (self.backoff_step + 1).min(RESTART_BACKOFF_SECONDS.len() - 1);
-        Duration::from_secs(RESTART_BACKOFF_SECONDS[idx])
+    fn next_session_id(&mut self) -> u64 {
+        self.last_session_id = self.last_session_id.wrapping_add(1);
+        self.last_session_id
+    }
+
+    fn begin_session(&mut self, worker: usize) -> u64 {
+        let session_id = self.next_session_id();
+        let slot = &mut self.workers[worker];
+        slot.session_id = session_id;
+        slot.circuit_probe = slot.state == WorkerState::HalfOpen;
+        slot.state = WorkerState::Starting;
+        slot.ipc_version = None;
+        slot.pending_commands.clear();
+        slot.restart_scheduled = false;
+        slot.heartbeat.reset();
+        session_id
+    }
+
+    /// Ends the worker's session and hands back its process, to be killed
+    /// once the lock is released.
+    fn stop_slot(&mut self, worker: usize) -> Option<Box<dyn WorkerProcess>> {
+        let session_id = self.next_session_id();
+        let slot = &mut self.workers[worker];
+        slot.session_id = session_id;
+        slot.restart_scheduled = false;
+        slot.circuit_probe = false;
+        slot.state = WorkerState::Stopped;
+        slot.ipc_version = None;
+        slot.incompatible_reason = None;
+        slot.pending_commands.clear();
+        slot.heartbeat.reset();
+        slot.child.take()
+    }
+
+    fn dispatch_targets(&self) -> Vec<DispatchTarget> {
+        self.workers
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_live())
+            .map(|(index, slot)| DispatchTarget {
+                worker: index,
+                group: slot.group.clone(),
+                max_concurrency: slot
+                    .capabilities
+                    .as_ref()
+                    .and_then(|capabilities| capabilities.max_concurrency),
+            })
+            .collect()
+    }
+
+    /// Hands tasks parked by a restart straight to the rest of the pool if
+    /// another worker is ready for them. Otherwise they wait for the next
+    /// hello.
+    fn reassign_parked(&mut self) -> Vec<TaskInfo> {
+        if self.workers.iter().any(WorkerSlot::is_live) {
+            self.dispatcher.take_awaiting_restart()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Deals with the tasks of a worker that will not come back. The rest of
+    /// the pool takes them over; with no other worker left, every unfinished
+    /// task fails as if the pool had been stopped.
+    fn abandon_worker_tasks(&mut self, worker: usize) -> Vec<TaskInfo> {
+        if !self.has_active_worker() {
+            self.dispatcher.fail_all_in_flight();
+            return Vec::new();
+        }
+        let mut parked = self.dispatcher.park_in_flight_for_restart(worker);
+        parked.extend(self.reassign_parked());
+        parked
+    }
+
+    /// Moves workers whose last task just ended from `Busy` back to `Ready`.
+    fn settle_idle_workers(&mut self) {
+        for (index, slot) in self.workers.iter_mut().enumerate() {
+            if slot.state == WorkerState::Busy && self.dispatcher.in_flight_on(index).is_empty() {
+                slot.state = WorkerState::Ready;
+            }
+        }
+    }
+
+    /// Whether an envelope's `v` shares the major of the version negotiated
+    /// with `worker`, or of the host's own version before the handshake.
+    fn speaks_ipc_version(&self, worker: usize, version: &str) -> bool {
+        let expected = self.workers[worker]
+            .ipc_version
+            .unwrap_or_else(IpcVersion::newest);
+        IpcVersion::parse(version).is_some_and(|version| version.major == expected.major)
+    }
+
+    /// Turns away a worker that speaks no IPC version the host does, after
+    /// its hello or any later envelope. Restarting would fail the same way,
+    /// so it stays down in `Incompatible` until the pool is stopped and
+    /// started again. A hello is answered with the reason. Returns its
+    /// process, to be killed once the lock is released, and its tasks, see
+    /// `abandon_worker_tasks`.
+    fn reject_incompatible(
+        &mut self,
+        worker: usize,
+        hello: Option<&Envelope>,
+        reason: String,
+    ) -> (Option<Box<dyn WorkerProcess>>, Vec<TaskInfo>) {
+        if let Some(hello) = hello {
+            let reply = Envelope::new(
+                MessageKind::Ack,
+                EVENT_WORKER_WELCOME,
+                json!({
+                    "accepted": false,
+                    "reason": reason,
+                    "min_ipc_version": MIN_IPC_VERSION,
+                    "max_ipc_version": IPC_VERSION,
+                }),
+            )
+            .in_reply_to(hello);
+            let _ = write_envelope(self, worker, &reply);
+        }
+        let session_id = self.next_session_id();
+        let slot = &mut self.workers[worker];
+        let child = slot.child.take();
+        slot.session_id = session_id;
+        slot.restart_scheduled = false;
+        slot.heartbeat.reset();
+        tracing::error!(worker, %reason, "rejected incompatible worker");
+        slot.state = WorkerState::Incompatible;
+        slot.incompatible_reason = Some(reason);
+        slot.pending_commands.clear();
+        (child, self.abandon_worker_tasks(worker))
+    }
+
+    /// Fails the task behind a `task.submit` the worker rejected or never
+    /// acknowledged. An unanswered `task.cancel` is left to the cancellation
+    /// grace period instead.
+    fn fail_command_task(
+        &mut self,
+        worker: usize,
+        command: &PendingCommand,
+        error: String,
+    ) -> Option<TaskInfo> {
+        if command.event != EVENT_TASK_SUBMIT {
+            return None;
+        }
+        let task_id = command.task_id.as_deref()?;
+        if self.dispatcher.worker_of(task_id) != Some(worker) {
+            return None;
+        }
+        self.dispatcher.fail_task(task_id, error)
     }
 
-    fn track_restart_and_check_breaker(&mut self) -> bool {
+    /// Tasks are stored under their project, so submissions for a project
+    /// that does not exist are refused before anything is queued.
+    fn ensure_project_exists(&self, project_id: &str) -> AppResult<()> {
+        let conn = self
+            .db
+            .lock()
+            .map_err(|e| AppError::lock_poisoned("database", e))?;
+        match projects_repo::get_by_id(&conn, project_id)? {
+            Some(_) => Ok(()),
+            None => Err(AppError::not_found("project", project_id)),
+        }
+    }
+
+    /// Rejects task types the worker did not declare. Before the first hello
+    /// nothing is known, so everything is accepted.
+    fn ensure_task_type_supported(&self, field: &str, task_type: &str) -> AppResult<()> {
+        match self.capabilities() {
+            Some(capabilities) if !capabilities.supports_task_type(task_type) => {
+                Err(AppError::validation(
+                    field,
+                    format!("worker does not support task type {task_type}"),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn next_backoff_delay(&mut self, worker: usize) -> Duration {
+        let slot = &mut self.workers[worker];
+        let delay = self.restart_policy.restart_backoff(slot.backoff_step);
+        slot.backoff_step = slot.backoff_step.saturating_add(1);
+        delay
+    }
+
+    fn log_restart(&mut self, worker: usize, reason: &str, circuit_broken: bool) {
+        if self.restart_log.len() == RESTART_LOG_CAPACITY {
+            self.restart_log.pop_front();
+        }
+        self.restart_log.push_back(RestartRecord {
+            at: Utc::now().to_rfc3339(),
+            worker,
+            reason: reason.to_string(),
+            circuit_broken,
+        });
+    }
+
+    fn track_restart_and_check_breaker(&mut self, worker: usize) -> bool {
         let now = Instant::now();
-        self.restart_history.push_back(now);
-        while self
-            .restart_history
+        let window = self.restart_policy.circuit_breaker_window();
+        let history = &mut self.workers[worker].restart_history;
+        history.push_back(now);
+        while history
             .front()
-            .is_some_and(|ts| now.duration_since(*ts) > CIRCUIT_BREAKER_WINDOW)
+            .is_some_and(|ts| now.duration_since(*ts) > window)
         {
-            self.restart_history.pop_front();
+            history.pop_front();
+        }
+        history.len() > self.restart_policy.circuit_breaker_max_restarts as usize
+    }
+
+    /// Opens the worker's circuit breaker; it stays open until reset or
+    /// until a whole breaker window has passed.
+    fn open_circuit(&mut self, worker: usize) {
+        let slot = &mut self.workers[worker];
+        slot.state = WorkerState::CircuitBroken;
+        slot.circuit_opened_at = Some(Instant::now());
+        slot.circuit_probe = false;
+    }
+
+    /// Lets one probe through the worker's open circuit breaker. The new
+    /// session id retires any threads left over from the broken session.
+    fn half_open_circuit(&mut self, worker: usize) {
+        tracing::info!(worker, "worker circuit breaker half-open");
+        let session_id = self.next_session_id();
+        let slot = &mut self.workers[worker];
+        slot.session_id = session_id;
+        slot.state = WorkerState::HalfOpen;
+        slot.circuit_opened_at = None;
+    }
+
+    fn half_open_cooled_down_breakers(&mut self) {
+        let window = self.restart_policy.circuit_breaker_window();
+        let cooled_down: Vec<usize> = self
+            .workers
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| {
+                slot.state == WorkerState::CircuitBroken
+                    && slot
+                        .circuit_opened_at
+                        .is_some_and(|opened_at| opened_at.elapsed() >= window)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        for worker in cooled_down {
+            self.half_open_circuit(worker);
+        }
+    }
+
+    /// Closes the breaker once its probe has been accepted in a hello.
+    fn close_circuit(&mut self, worker: usize) {
+        let slot = &mut self.workers[worker];
+        if slot.circuit_probe {
+            tracing::info!(worker, "worker circuit breaker closed");
+            slot.circuit_probe = false;
+            slot.restart_history.clear();
+        }
+    }
+
+    /// A probe that cannot even be launched opens the breaker again.
+    fn end_failed_launch(&mut self, worker: usize) {
+        if self.workers[worker].circuit_probe {
+            self.open_circuit(worker);
+        } else {
+            self.workers[worker].state = WorkerState::Stopped;
         }
-        self.restart_history.len() > CIRCUIT_BREAKER_MAX_RESTARTS
     }
 
-    fn mark_ready(&mut self) {
-        self.restart_scheduled = false;
-        self.backoff_step = 0;
-        self.state = if self.dispatcher.in_flight_count() > 0 {
+    /// Settles a worker into Ready or Busy. Only a worker that got through
+    /// its handshake and is not on its way out is touched.
+    fn mark_ready(&mut self, worker: usize) {
+        let busy = !self.dispatcher.in_flight_on(worker).is_empty();
+        let slot = &mut self.workers[worker];
+        let settled = matches!(
+            slot.state,
+            WorkerState::Starting | WorkerState::Ready | WorkerState::Busy
+        );
+        if slot.child.is_none() || slot.ipc_version.is_none() || !settled {
+            return;
+        }
+        slot.restart_scheduled = false;
+        slot.backoff_step = 0;
+        slot.state = if busy {
             WorkerState::Busy
         } else {
             WorkerState::Ready
         };
     }
+
+    /// Marks a ready worker busy; see `mark_ready` for who is left alone.
+    fn mark_busy(&mut self, worker: usize) {
+        let slot = &mut self.workers[worker];
+        if slot.is_live() {
+            slot.state = WorkerState::Busy;
+        }
+    }
 }
 
-fn lock(shared: &SharedWorkerManager) -> Result<MutexGuard<'_, WorkerManager>, String> {
+fn lock(shared: &SharedWorkerManager) -> AppResult<MutexGuard<'_, WorkerManager>> {
     shared
         .lock()
-        .map_err(|e| format!("worker manager lock poisoned: {e}"))
+        .map_err(|e| AppError::lock_poisoned("worker manager", e))
 }
 
-fn write_envelope(manager: &mut WorkerManager, envelope: &Envelope) -> Result<(), String> {
-    let child = manager
-        .child
-        .as_mut()
-        .ok_or_else(|| "worker sidecar is not running".to_string())?;
-
+fn write_envelope(
+    manager: &mut WorkerManager,
+    worker: usize,
+    envelope: &Envelope,
+) -> AppResult<()> {
+    let slot = manager
+        .workers
+        .get_mut(worker)
+        .filter(|slot| slot.child.is_some())
+        .ok_or_else(|| {
+            AppError::new(ErrorCode::WorkerNotRunning, "worker sidecar is not running")
+        })?;
+
+    let envelope = match slot.ipc_version {
+        Some(version) => Envelope {
+            v: version.to_string(),
+            ..envelope.clone()
+        },
+        None => envelope.clone(),
+    };
     let line = envelope
         .to_ndjson_line()
-        .map_err(|e| format!("failed to encode IPC envelope: {e}"))?;
+        .map_err(|e| AppError::internal(format!("failed to encode IPC envelope: {e}")))?;
+
+    if let Some(child) = slot.child.as_mut() {
+        child
+            .write(line.as_bytes())
+            .map_err(|e| AppError::worker(format!("failed to write to worker stdin: {e}")))?;
+    }
+    if envelope.kind == MessageKind::Command {
+        slot.pending_commands.track(&envelope);
+    }
+    manager
+        .journal
+        .record(worker, EnvelopeDirection::ToWorker, &envelope);
+    Ok(())
+}
 
-    child
-        .write(line.as_bytes())
-        .map_err(|e| format!("failed to write to worker stdin: {e}"))
+fn submit_envelope(task: &TaskInfo) -> AppResult<Envelope> {
+    let payload = serde_json::to_value(task.to_payload())
+        .map_err(|e| AppError::internal(format!("failed to serialize task payload: {e}")))?;
+    Ok(
+        Envelope::new(MessageKind::Command, EVENT_TASK_SUBMIT, payload)
+            .with_trace_id(task.trace_id.as_deref()),
+    )
+}
+
+/// Hands queued tasks to the pool as far as the concurrency limits allow.
+/// A worker gets nothing until it has said hello.
+fn pump_queue<R: Runtime>(shared: &SharedWorkerManager, app: &AppHandle<R>) -> AppResult<()> {
+    let dispatched = {
+        let mut m = lock(shared)?;
+        let targets = m.dispatch_targets();
+        if targets.is_empty() {
+            return Ok(());
+        }
+        let dispatched = m.dispatcher.take_dispatchable(&targets);
+        if dispatched.is_empty() {
+            return Ok(());
+        }
+        // A task whose write fails stays in flight and is parked by the
+        // restart that a broken stdin leads to; the other workers carry on.
+        for (worker, task) in &dispatched {
+            m.workers[*worker].state = WorkerState::Busy;
+            if let Err(err) = write_envelope(&mut m, *worker, &submit_envelope(task)?) {
+                tracing::warn!(
+                    worker = *worker,
+                    task_id = %task.task_id,
+                    trace_id = task.trace_label(),
+                    %err,
+                    "failed to submit task"
+                );
+            }
+        }
+        dispatched
+    };
+
+    emit_status(app, shared);
+    for (_, task) in dispatched {
+        let _ = app.emit("task:progress", task);
+    }
+    Ok(())
+}
+
+fn cancel_envelope(task_id: &str, trace_id: Option<&str>) -> Envelope {
+    Envelope::new(
+        MessageKind::Command,
+        EVENT_TASK_CANCEL,
+        json!({ "task_id": task_id }),
+    )
+    .with_trace_id(trace_id)
+}
+
+/// Checks that node keys are unique, every dependency names another node
+/// and the dependencies form no cycle.
+fn validate_graph(nodes: &[TaskGraphNodeInput]) -> AppResult<()> {
+    if nodes.is_empty() {
+        return Err(AppError::validation(
+            "nodes",
+            "a task graph needs at least one node",
+        ));
+    }
+
+    let mut keys = HashSet::new();
+    for node in nodes {
+        if node.key.trim().is_empty() {
+            return Err(AppError::validation("nodes", "node key cannot be empty"));
+        }
+        if node.task_type.trim().is_empty() {
+            return Err(AppError::validation(
+                "nodes",
+                format!("node {} has an empty task_type", node.key),
+            ));
+        }
+        if !keys.insert(node.key.as_str()) {
+            return Err(AppError::validation(
+                "nodes",
+                format!("duplicate node key {}", node.key),
+            ));
+        }
+    }
+
+    let mut indegree: HashMap<&str, usize> = HashMap::new();
+    let mut children: HashMap<&str, Vec<&str>> = HashMap::new();
+    for node in nodes {
+        indegree.insert(&node.key, node.depends_on.len());
+        for parent in &node.depends_on {
+            if !keys.contains(parent.as_str()) || *parent == node.key {
+                return Err(AppError::validation(
+                    "nodes",
+                    format!("node {} depends on unknown node {parent}", node.key),
+                ));
+            }
+            children.entry(parent).or_default().push(&node.key);
+        }
+    }
+
+    let mut ready: Vec<&str> = indegree
+        .iter()
+        .filter(|(_, degree)| **degree == 0)
+        .map(|(key, _)| *key)
+        .collect();
+    let mut visited = 0;
+    while let Some(key) = ready.pop() {
+        visited += 1;
+        for child in children.get(key).into_iter().flatten() {
+            if let Some(degree) = indegree.get_mut(child) {
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.push(child);
+                }
+            }
+        }
+    }
+    if visited != nodes.len() {
+        return Err(AppError::validation(
+            "nodes",
+            "task graph dependencies contain a cycle",
+        ));
+    }
+    Ok(())
+}
+
+/// Announces tasks that lost their worker: parked for the next session,
+/// handed to another worker or settled for good.
+fn emit_parked<R: Runtime>(app: &AppHandle<R>, tasks: Vec<TaskInfo>) {
+    for task in tasks {
+        let event = match task.status {
+            TaskStatus::Failed => "task:failed",
+            TaskStatus::Cancelled => "task:cancelled",
+            _ => "task:retry_scheduled",
+        };
+        let _ = app.emit(event, task);
+    }
+}
+
+/// Announces graph nodes that were released or cancelled downstream of
+/// another task.
+fn emit_task_updates<R: Runtime>(app: &AppHandle<R>, tasks: Vec<TaskInfo>) {
+    for task in tasks {
+        let event = if task.status == TaskStatus::Cancelled {
+            "task:cancelled"
+        } else {
+            "task:progress"
+        };
+        let _ = app.emit(event, task);
+    }
 }
 
 fn emit_status<R: Runtime>(app: &AppHandle<R>, shared: &SharedWorkerManager) {
@@ -335,31 +1528,27 @@ fn emit_status<R: Runtime>(app: &AppHandle<R>, shared: &SharedWorkerManager) {
     }
 }
 
-fn send_to_worker(shared: &SharedWorkerManager, envelope: &Envelope) -> Result<(), String> {
-    let mut m = lock(shared)?;
-    write_envelope(&mut m, envelope)
-}
-
 fn spawn_event_listener<R: Runtime + 'static>(
     shared: SharedWorkerManager,
     app: AppHandle<R>,
-    mut receiver: tauri::async_runtime::Receiver<CommandEvent>,
+    receiver: Receiver<WorkerEvent>,
+    worker: usize,
     session_id: u64,
+    mut stderr_log: Option<File>,
 ) {
     thread::spawn(move || {
         let mut stdout_buffer = String::new();
         let mut restart_reason: Option<String> = None;
 
         loop {
-            let event = tauri::async_runtime::block_on(receiver.recv());
-            let Some(event) = event else { break };
+            let Ok(event) = receiver.recv() else { break };
 
-            if !is_session_active(&shared, session_id) {
+            if !is_session_active(&shared, worker, session_id) {
                 return;
             }
 
             match event {
-                CommandEvent::Stdout(bytes) => {
+                WorkerEvent::Stdout(bytes) => {
                     stdout_buffer.push_str(&String::from_utf8_lossy(&bytes));
                     if stdout_buffer.len() > MAX_STDOUT_BUFFER_BYTES {
                         restart_reason = Some(format!(
@@ -368,27 +1557,36 @@ fn spawn_event_listener<R: Runtime + 'static>(
                         ));
                         break;
                     }
-                    if let Err(err) = drain_stdout(&shared, &app, session_id, &mut stdout_buffer) {
+                    if let Err(err) =
+                        drain_stdout(&shared, &app, worker, session_id, &mut stdout_buffer)
+                    {
                         restart_reason = Some(err);
                         break;
                     }
                 }
-                CommandEvent::Stderr(bytes) => {
-                    let msg = String::from_utf8_lossy(&bytes);
-                    eprintln!("[dy-worker stderr] {}", msg.trim());
+                WorkerEvent::Stderr(bytes) => {
+                    let written = stderr_log.as_mut().map(|file| file.write_all(&bytes));
+                    if !matches!(written, Some(Ok(()))) {
+                        let msg = String::from_utf8_lossy(&bytes);
+                        tracing::info!(
+                            target: "dy_worker::stderr",
+                            worker,
+                            session_id,
+                            "{}",
+                            msg.trim()
+                        );
+                    }
                 }
-                CommandEvent::Error(error) => {
+                WorkerEvent::Error(error) => {
                     restart_reason = Some(format!("worker error: {error}"));
                     break;
                 }
-                CommandEvent::Terminated(payload) => {
+                WorkerEvent::Terminated { code, signal } => {
                     restart_reason = Some(format!(
-                        "worker exited with code {:?}, signal {:?}",
-                        payload.code, payload.signal
+                        "worker exited with code {code:?}, signal {signal:?}"
                     ));
                     break;
                 }
-                _ => {}
             }
         }
 
@@ -398,7 +1596,7 @@ fn spawn_event_listener<R: Runtime + 'static>(
             match deserialize_ndjson(&format!("{trailing}\n")) {
                 Ok(messages) => {
                     for msg in messages {
-                        handle_envelope(&shared, &app, session_id, msg);
+                        handle_envelope(&shared, &app, worker, session_id, msg);
                     }
                 }
                 Err(err) => {
@@ -408,10 +1606,10 @@ fn spawn_event_listener<R: Runtime + 'static>(
             }
         }
 
-        if mark_unhealthy(&shared, session_id) {
+        if mark_unhealthy(&shared, worker, session_id) {
             emit_status(&app, &shared);
             let reason = restart_reason.unwrap_or_else(|| "worker stream closed".into());
-            schedule_restart(shared, app, reason);
+            schedule_restart(shared, app, worker, reason);
         }
     });
 }
@@ -419,6 +1617,7 @@ fn spawn_event_listener<R: Runtime + 'static>(
 fn spawn_heartbeat_monitor<R: Runtime + 'static>(
     shared: SharedWorkerManager,
     app: AppHandle<R>,
+    worker: usize,
     session_id: u64,
 ) {
     thread::spawn(move || loop {
@@ -427,43 +1626,135 @@ fn spawn_heartbeat_monitor<R: Runtime + 'static>(
                 Ok(m) => m,
                 Err(_) => return,
             };
-            if m.session_id != session_id {
+            if !m.is_current_session(worker, session_id) {
                 return;
             }
-            m.heartbeat.interval()
+            m.workers[worker].heartbeat.interval()
         };
 
         thread::sleep(interval);
 
         let mut should_restart = false;
+        let mut expired = Vec::new();
+        let mut abandoned = Vec::new();
+        let mut downstream = Vec::new();
         {
             let mut m = match lock(&shared) {
                 Ok(m) => m,
                 Err(_) => return,
             };
-            if m.session_id != session_id {
+            if !m.is_current_session(worker, session_id) {
                 return;
             }
-            if matches!(m.state, WorkerState::Stopped | WorkerState::CircuitBroken) {
+            if matches!(
+                m.workers[worker].state,
+                WorkerState::Stopped
+                    | WorkerState::CircuitBroken
+                    | WorkerState::HalfOpen
+                    | WorkerState::Incompatible
+            ) {
                 return;
             }
-            if m.heartbeat.check() == HeartbeatCheck::Unhealthy {
-                m.state = WorkerState::Unhealthy;
-                should_restart = true;
+            if m.workers[worker].heartbeat.check() == HeartbeatCheck::Unhealthy {
+                tracing::warn!(worker, session_id, "worker heartbeat timed out");
+                m.workers[worker].state = WorkerState::Unhealthy;
+                should_restart = true;
+            } else {
+                expired = m.dispatcher.expire_stuck_tasks(worker);
+                for (task_id, _) in &expired {
+                    let trace_id = m.dispatcher.trace_id(task_id).map(str::to_string);
+                    let envelope = cancel_envelope(task_id, trace_id.as_deref());
+                    if let Err(err) = write_envelope(&mut m, worker, &envelope) {
+                        tracing::warn!(
+                            %task_id,
+                            trace_id = trace_id.as_deref().unwrap_or("-"),
+                            %err,
+                            "failed to cancel timed out task"
+                        );
+                    }
+                }
+                abandoned = m.dispatcher.expire_cancellations(worker);
+                let ack_timeout = m.workers[worker].pending_commands.timeout().as_secs();
+                for command in m.workers[worker].pending_commands.expire() {
+                    tracing::warn!(
+                        worker,
+                        event = %command.event,
+                        task_id = command.task_id.as_deref().unwrap_or("-"),
+                        "worker did not acknowledge command"
+                    );
+                    let error = format!(
+                        "worker did not acknowledge {} within {ack_timeout}s",
+                        command.event
+                    );
+                    let failed = m.fail_command_task(worker, &command, error);
+                    if let Some(task) = &failed {
+                        // The worker may have taken the task without a word;
+                        // make sure it does not keep running unseen.
+                        let envelope = cancel_envelope(&task.task_id, task.trace_id.as_deref());
+                        if let Err(err) = write_envelope(&mut m, worker, &envelope) {
+                            tracing::warn!(
+                                task_id = %task.task_id,
+                                trace_id = task.trace_label(),
+                                %err,
+                                "failed to cancel unacknowledged task"
+                            );
+                        }
+                    }
+                    abandoned.extend(failed);
+                }
+                downstream = m.dispatcher.take_graph_updates();
+                m.settle_idle_workers();
+            }
+        }
+
+        if should_restart {
+            emit_status(&app, &shared);
+            schedule_restart(
+                Arc::clone(&shared),
+                app.clone(),
+                worker,
+                "heartbeat timeout".into(),
+            );
+            return;
+        }
+        if !expired.is_empty() || !abandoned.is_empty() {
+            emit_expired(&shared, &app, expired, abandoned, downstream);
+        }
+    });
+}
+
+fn emit_expired<R: Runtime + 'static>(
+    shared: &SharedWorkerManager,
+    app: &AppHandle<R>,
+    expired: Vec<(String, TaskResolution)>,
+    abandoned: Vec<TaskInfo>,
+    downstream: Vec<TaskInfo>,
+) {
+    for (task_id, resolution) in expired {
+        match resolution {
+            TaskResolution::Finished(task) => {
+                let _ = app.emit("task:failed", task);
+            }
+            TaskResolution::RetryScheduled { task, delay } => {
+                let _ = app.emit("task:retry_scheduled", task);
+                spawn_retry_timer(Arc::clone(shared), app.clone(), task_id, delay);
             }
         }
-
-        if should_restart {
-            emit_status(&app, &shared);
-            schedule_restart(Arc::clone(&shared), app.clone(), "heartbeat timeout".into());
-            return;
-        }
-    });
+    }
+    for task in abandoned {
+        let _ = app.emit("task:failed", task);
+    }
+    emit_task_updates(app, downstream);
+    if let Err(err) = pump_queue(shared, app) {
+        tracing::error!(%err, "failed to dispatch queued tasks");
+    }
+    emit_status(app, shared);
 }
 
 fn drain_stdout<R: Runtime + 'static>(
     shared: &SharedWorkerManager,
     app: &AppHandle<R>,
+    worker: usize,
     session_id: u64,
     buffer: &mut String,
 ) -> Result<(), String> {
@@ -479,7 +1770,7 @@ fn drain_stdout<R: Runtime + 'static>(
         match deserialize_ndjson(&format!("{line}\n")) {
             Ok(messages) => {
                 for msg in messages {
-                    handle_envelope(shared, app, session_id, msg);
+                    handle_envelope(shared, app, worker, session_id, msg);
                 }
             }
             Err(err) => {
@@ -493,24 +1784,22 @@ fn drain_stdout<R: Runtime + 'static>(
 fn handle_envelope<R: Runtime + 'static>(
     shared: &SharedWorkerManager,
     app: &AppHandle<R>,
+    worker: usize,
     session_id: u64,
     envelope: Envelope,
 ) {
-    if envelope.v != IPC_VERSION {
-        schedule_restart(
-            Arc::clone(shared),
-            app.clone(),
-            format!("unsupported IPC version from worker: {}", envelope.v),
-        );
-        return;
-    }
-
     let mut status_changed = false;
     let mut progress_event: Option<TaskInfo> = None;
     let mut completed_event: Option<TaskInfo> = None;
     let mut failed_event: Option<TaskInfo> = None;
-    let mut welcome_reply: Option<Envelope> = None;
+    let mut cancelled_event: Option<TaskInfo> = None;
+    let mut requeued: Vec<TaskInfo> = Vec::new();
+    let mut parked: Vec<TaskInfo> = Vec::new();
+    let mut rejected: Option<Box<dyn WorkerProcess>> = None;
+    let mut graph_updates: Vec<TaskInfo> = Vec::new();
+    let mut retry_event: Option<(TaskInfo, Duration)> = None;
     let mut restart_reason: Option<String> = None;
+    let mut asset_sink: Option<(SharedConnection, MediaStore)> = None;
 
     {
         let mut m = match lock(shared) {
@@ -518,38 +1807,130 @@ fn handle_envelope<R: Runtime + 'static>(
             Err(_) => return,
         };
 
-        if m.session_id != session_id {
+        if !m.is_current_session(worker, session_id) {
             return;
         }
+        m.journal
+            .record(worker, EnvelopeDirection::FromWorker, &envelope);
+        // The hello's own `v` is only a fallback for its declared versions.
+        if envelope.event != EVENT_WORKER_HELLO && !m.speaks_ipc_version(worker, &envelope.v) {
+            let reason = format!(
+                "worker sent {} in IPC {}, host speaks {MIN_IPC_VERSION} to {IPC_VERSION}",
+                envelope.event, envelope.v
+            );
+            let (child, parked) = m.reject_incompatible(worker, None, reason);
+            let downstream = m.dispatcher.take_graph_updates();
+            drop(m);
+            if let Some(child) = child {
+                let _ = child.kill();
+            }
+            emit_status(app, shared);
+            emit_parked(app, parked);
+            emit_task_updates(app, downstream);
+            if let Err(err) = pump_queue(shared, app) {
+                tracing::error!(%err, "failed to dispatch queued tasks");
+            }
+            return;
+        }
+
+        if envelope.kind == MessageKind::Event {
+            if let Some(task_id) = envelope.payload.get("task_id").and_then(|id| id.as_str()) {
+                m.workers[worker]
+                    .pending_commands
+                    .resolve_task(EVENT_TASK_SUBMIT, task_id);
+            }
+        }
 
         match envelope.event.as_str() {
+            _ if envelope.kind == MessageKind::Ack => {
+                if let Some(msg_id) = &envelope.reply_to {
+                    m.workers[worker].pending_commands.resolve(msg_id);
+                }
+            }
+            _ if envelope.kind == MessageKind::Error && envelope.reply_to.is_some() => {
+                let command = envelope
+                    .reply_to
+                    .as_deref()
+                    .and_then(|msg_id| m.workers[worker].pending_commands.resolve(msg_id));
+                if let Some(command) = command {
+                    let reason = envelope
+                        .payload
+                        .get("message")
+                        .and_then(|message| message.as_str())
+                        .unwrap_or("no reason given");
+                    let error = format!("worker rejected {}: {reason}", command.event);
+                    failed_event = m.fail_command_task(worker, &command, error);
+                    if failed_event.is_some() {
+                        m.mark_ready(worker);
+                        status_changed = true;
+                    }
+                }
+            }
             EVENT_WORKER_HELLO => {
-                m.heartbeat.mark_heartbeat();
-                m.mark_ready();
-                status_changed = true;
-                welcome_reply = Some(Envelope::new(
-                    MessageKind::Ack,
-                    EVENT_WORKER_WELCOME,
-                    json!({ "accepted": true }),
-                ));
+                match serde_json::from_value::<WorkerCapabilities>(envelope.payload.clone()) {
+                    Ok(capabilities) => {
+                        let offered = if capabilities.ipc_versions.is_empty() {
+                            vec![envelope.v.clone()]
+                        } else {
+                            capabilities.ipc_versions.clone()
+                        };
+                        if let Some(version) = negotiate_ipc_version(&offered) {
+                            let slot = &mut m.workers[worker];
+                            slot.heartbeat.mark_heartbeat();
+                            slot.ipc_version = Some(version);
+                            slot.capabilities = Some(capabilities);
+                            // Welcome the worker before it can be dispatched
+                            // to, so no task.submit ever overtakes the reply.
+                            let welcome = Envelope::new(
+                                MessageKind::Ack,
+                                EVENT_WORKER_WELCOME,
+                                json!({ "accepted": true, "ipc_version": version.to_string() }),
+                            )
+                            .in_reply_to(&envelope);
+                            if let Err(err) = write_envelope(&mut m, worker, &welcome) {
+                                tracing::warn!(worker, %err, "failed to welcome worker");
+                            }
+                            requeued = m.dispatcher.take_awaiting_restart();
+                            m.close_circuit(worker);
+                            m.mark_ready(worker);
+                            tracing::info!(
+                                worker,
+                                session_id,
+                                ipc_version = %version,
+                                build = build_version(m.workers[worker].capabilities.as_ref()),
+                                "worker handshake accepted"
+                            );
+                        } else {
+                            (rejected, parked) = m.reject_incompatible(worker, Some(&envelope), format!(
+                                "worker speaks IPC {}, host speaks {MIN_IPC_VERSION} to {IPC_VERSION}",
+                                offered.join(", ")
+                            ));
+                        }
+                        status_changed = true;
+                    }
+                    Err(err) => {
+                        restart_reason = Some(format!("invalid worker.hello payload: {err}"));
+                    }
+                }
             }
+            // Only a valid hello makes the worker ready, so that tasks wait
+            // until its capabilities and IPC version are known.
             EVENT_WORKER_HEARTBEAT => {
-                m.heartbeat.mark_heartbeat();
-                m.mark_ready();
+                m.workers[worker].heartbeat.mark_heartbeat();
                 status_changed = true;
             }
             EVENT_TASK_STARTED => {
                 if let Some(task_id) = envelope.payload.get("task_id").and_then(|id| id.as_str()) {
-                    progress_event = m.dispatcher.mark_started(task_id);
+                    progress_event = m.dispatcher.mark_started(worker, task_id);
                 }
-                m.state = WorkerState::Busy;
+                m.mark_busy(worker);
                 status_changed = true;
             }
             EVENT_TASK_PROGRESS => {
                 match serde_json::from_value::<ProgressPayload>(envelope.payload.clone()) {
                     Ok(payload) => {
-                        progress_event = m.dispatcher.apply_progress(payload);
-                        m.state = WorkerState::Busy;
+                        progress_event = m.dispatcher.apply_progress(worker, payload);
+                        m.mark_busy(worker);
                         status_changed = true;
                     }
                     Err(err) => {
@@ -562,13 +1943,25 @@ fn handle_envelope<R: Runtime + 'static>(
                     Ok(payload) => {
                         let is_failure = envelope.event == EVENT_TASK_FAILED
                             || payload.error.is_some();
-                        let info = m.dispatcher.apply_result(payload);
-                        if is_failure {
-                            failed_event = info;
-                        } else {
-                            completed_event = info;
+                        match m.dispatcher.apply_result(payload) {
+                            Some(TaskResolution::RetryScheduled { task, delay }) => {
+                                retry_event = Some((task, delay));
+                            }
+                            Some(TaskResolution::Finished(info))
+                                if info.status == TaskStatus::Cancelled =>
+                            {
+                                cancelled_event = Some(info);
+                            }
+                            Some(TaskResolution::Finished(info)) if is_failure => {
+                                failed_event = Some(info);
+                            }
+                            Some(TaskResolution::Finished(info)) => {
+                                completed_event = Some(info);
+                                asset_sink = Some((Arc::clone(&m.db), m.media_store.clone()));
+                            }
+                            None => {}
                         }
-                        m.mark_ready();
+                        m.mark_ready(worker);
                         status_changed = true;
                     }
                     Err(err) => {
@@ -576,36 +1969,147 @@ fn handle_envelope<R: Runtime + 'static>(
                     }
                 }
             }
+            EVENT_TASK_CANCELLED => {
+                if let Some(task_id) = envelope.payload.get("task_id").and_then(|id| id.as_str()) {
+                    cancelled_event = m.dispatcher.confirm_cancelled(task_id);
+                }
+                m.mark_ready(worker);
+                status_changed = true;
+            }
             _ if envelope.kind == MessageKind::Error => {
                 restart_reason = Some(format!("worker error event: {}", envelope.event));
             }
             _ => {}
         }
+        graph_updates = m.dispatcher.take_graph_updates();
     }
 
-    if let Some(reply) = welcome_reply {
-        let _ = send_to_worker(shared, &reply);
+    if let Some(child) = rejected {
+        let _ = child.kill();
+    }
+    for task in requeued {
+        let _ = app.emit("task:progress", task);
     }
+    emit_parked(app, parked);
     if status_changed {
         emit_status(app, shared);
+        if let Err(err) = pump_queue(shared, app) {
+            tracing::error!(%err, "failed to dispatch queued tasks");
+        }
     }
     if let Some(payload) = progress_event {
         let _ = app.emit("task:progress", payload);
     }
     if let Some(payload) = completed_event {
-        let _ = app.emit("task:completed", payload);
+        let _ = app.emit("task:completed", &payload);
+        if let Some((db, media_store)) = asset_sink {
+            spawn_output_registration(db, media_store, app.clone(), payload);
+        }
     }
     if let Some(payload) = failed_event {
         let _ = app.emit("task:failed", payload);
     }
+    if let Some(payload) = cancelled_event {
+        let _ = app.emit("task:cancelled", payload);
+    }
+    emit_task_updates(app, graph_updates);
+    if let Some((task, delay)) = retry_event {
+        let task_id = task.task_id.clone();
+        let _ = app.emit("task:retry_scheduled", task);
+        spawn_retry_timer(Arc::clone(shared), app.clone(), task_id, delay);
+    }
     if let Some(reason) = restart_reason {
-        schedule_restart(Arc::clone(shared), app.clone(), reason);
+        schedule_restart(Arc::clone(shared), app.clone(), worker, reason);
+    }
+}
+
+fn spawn_output_registration<R: Runtime + 'static>(
+    db: SharedConnection,
+    media_store: MediaStore,
+    app: AppHandle<R>,
+    task: TaskInfo,
+) {
+    thread::spawn(move || match register_task_outputs(&db, &media_store, &task) {
+        Ok(assets) if !assets.is_empty() => {
+            let _ = app.emit(
+                "asset:registered",
+                AssetsRegisteredEvent {
+                    task_id: task.task_id,
+                    project_id: task.project_id,
+                    trace_id: task.trace_id,
+                    assets,
+                },
+            );
+        }
+        Ok(_) => {}
+        Err(err) => {
+            tracing::error!(
+                task_id = %task.task_id,
+                trace_id = task.trace_label(),
+                %err,
+                "failed to register task outputs"
+            );
+        }
+    });
+}
+
+fn spawn_retry_timer<R: Runtime + 'static>(
+    shared: SharedWorkerManager,
+    app: AppHandle<R>,
+    task_id: String,
+    delay: Duration,
+) {
+    thread::spawn(move || {
+        thread::sleep(delay);
+        if let Err(err) = resume_retry(&shared, &app, &task_id) {
+            tracing::error!(%task_id, %err, "failed to retry task");
+        }
+    });
+}
+
+/// Requeues a task whose retry backoff has elapsed, starting the pool if
+/// nothing is running. Does nothing if the task was cancelled meanwhile.
+fn resume_retry<R: Runtime + 'static>(
+    shared: &SharedWorkerManager,
+    app: &AppHandle<R>,
+    task_id: &str,
+) -> AppResult<()> {
+    let need_start = {
+        let m = lock(shared)?;
+        if !m.dispatcher.has_scheduled_retry(task_id) {
+            return Ok(());
+        }
+        m.needs_start()
+    };
+    if need_start {
+        if let Err(err) = WorkerManager::start(shared, app) {
+            let (failed, downstream) = {
+                let mut m = lock(shared)?;
+                let failed = m
+                    .dispatcher
+                    .fail_task(task_id, format!("retry failed: {}", err.message));
+                (failed, m.dispatcher.take_graph_updates())
+            };
+            if let Some(task) = failed {
+                let _ = app.emit("task:failed", task);
+            }
+            emit_task_updates(app, downstream);
+            return Err(err);
+        }
     }
+
+    let Some(task) = lock(shared)?.dispatcher.resume_retry(task_id) else {
+        return Ok(());
+    };
+
+    let _ = app.emit("task:progress", task);
+    pump_queue(shared, app)
 }
 
 fn schedule_restart<R: Runtime + 'static>(
     shared: SharedWorkerManager,
     app: AppHandle<R>,
+    worker: usize,
     reason: String,
 ) {
     let decision = {
@@ -614,74 +2118,791 @@ fn schedule_restart<R: Runtime + 'static>(
             Err(_) => return,
         };
 
-        if m.restart_scheduled
-            || matches!(m.state, WorkerState::Stopped | WorkerState::CircuitBroken)
+        let Some(slot) = m.workers.get(worker) else {
+            return;
+        };
+        if slot.restart_scheduled
+            || matches!(
+                slot.state,
+                WorkerState::Stopped
+                    | WorkerState::CircuitBroken
+                    | WorkerState::HalfOpen
+                    | WorkerState::Incompatible
+            )
         {
             return;
         }
 
-        if m.track_restart_and_check_breaker() {
-            m.state = WorkerState::CircuitBroken;
-            m.restart_scheduled = false;
-            RestartDecision::CircuitBroken
+        // A half-open breaker gives its probe no second chance.
+        let probe_failed = slot.circuit_probe;
+        let circuit_broken = m.track_restart_and_check_breaker(worker) || probe_failed;
+        m.log_restart(worker, &reason, circuit_broken);
+        if circuit_broken {
+            tracing::error!(
+                worker,
+                %reason,
+                probe_failed,
+                restarts = m.workers[worker].restart_history.len(),
+                "worker circuit breaker tripped"
+            );
+            m.open_circuit(worker);
+            let slot = &mut m.workers[worker];
+            slot.restart_scheduled = false;
+            slot.pending_commands.clear();
+            let child = slot.child.take();
+            let parked = m.abandon_worker_tasks(worker);
+            RestartDecision::CircuitBroken {
+                child,
+                parked,
+                downstream: m.dispatcher.take_graph_updates(),
+            }
         } else {
-            m.state = WorkerState::Unhealthy;
-            m.restart_scheduled = true;
+            let delay = m.next_backoff_delay(worker);
+            tracing::warn!(worker, %reason, ?delay, "scheduling worker restart");
+            let slot = &mut m.workers[worker];
+            slot.state = WorkerState::Unhealthy;
+            slot.restart_scheduled = true;
             RestartDecision::Delayed {
-                delay: m.next_backoff_delay(),
-                session_id: m.session_id,
+                delay,
+                session_id: slot.session_id,
             }
         }
     };
 
     emit_status(&app, &shared);
 
-    if let RestartDecision::Delayed { delay, session_id } = decision {
-        thread::spawn(move || {
-            thread::sleep(delay);
-            let should_restart = {
-                let mut m = match lock(&shared) {
-                    Ok(m) => m,
-                    Err(_) => return,
+    match decision {
+        RestartDecision::CircuitBroken {
+            child,
+            parked,
+            downstream,
+        } => {
+            if let Some(child) = child {
+                let _ = child.kill();
+            }
+            emit_parked(&app, parked);
+            emit_task_updates(&app, downstream);
+            if let Err(err) = pump_queue(&shared, &app) {
+                tracing::error!(%err, "failed to dispatch queued tasks");
+            }
+        }
+        RestartDecision::Delayed { delay, session_id } => {
+            thread::spawn(move || {
+                thread::sleep(delay);
+                let should_restart = {
+                    let mut m = match lock(&shared) {
+                        Ok(m) => m,
+                        Err(_) => return,
+                    };
+                    if !m.is_current_session(worker, session_id)
+                        || !m.workers[worker].restart_scheduled
+                        || m.workers[worker].state != WorkerState::Unhealthy
+                    {
+                        false
+                    } else {
+                        m.workers[worker].restart_scheduled = false;
+                        true
+                    }
                 };
-                if m.session_id != session_id
-                    || !m.restart_scheduled
-                    || m.state != WorkerState::Unhealthy
-                {
-                    false
-                } else {
-                    m.restart_scheduled = false;
-                    true
+                if should_restart {
+                    if let Err(err) = WorkerManager::restart_worker(&shared, &app, worker, &reason)
+                    {
+                        tracing::error!(worker, %reason, %err, "failed to restart worker");
+                    }
                 }
+            });
+        }
+    }
+}
+
+fn mark_unhealthy(shared: &SharedWorkerManager, worker: usize, session_id: u64) -> bool {
+    let (child, unhealthy) = {
+        let mut m = match lock(shared) {
+            Ok(m) => m,
+            Err(_) => return false,
+        };
+
+        if !m.is_current_session(worker, session_id) {
+            return false;
+        }
+
+        let slot = &mut m.workers[worker];
+        let unhealthy = !matches!(
+            slot.state,
+            WorkerState::Stopped
+                | WorkerState::CircuitBroken
+                | WorkerState::HalfOpen
+                | WorkerState::Incompatible
+        );
+        if unhealthy {
+            slot.state = WorkerState::Unhealthy;
+        }
+        (slot.child.take(), unhealthy)
+    };
+
+    if let Some(child) = child {
+        let _ = child.kill();
+    }
+    unhealthy
+}
+
+fn build_version(capabilities: Option<&WorkerCapabilities>) -> &str {
+    capabilities
+        .and_then(|capabilities| capabilities.build.as_ref())
+        .map_or("-", |build| build.version.as_str())
+}
+
+fn is_session_active(shared: &SharedWorkerManager, worker: usize, session_id: u64) -> bool {
+    lock(shared).is_ok_and(|m| m.is_current_session(worker, session_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, env};
+
+    use tauri::{
+        test::{mock_app, MockRuntime},
+        App,
+    };
+
+    use super::*;
+    use crate::{
+        database,
+        models::{CreateProjectInput, WorkerGroupConfig, WorkerProgram},
+        repository::{projects_repo, tasks_repo, worker_settings_repo},
+        worker::fake::{wait_until, FakeLauncher, FakeWorker},
+    };
+
+    const WAIT: Duration = Duration::from_secs(5);
+
+    struct Harness {
+        // Keeps the mock app alive for the handle below.
+        _app: App<MockRuntime>,
+        app: AppHandle<MockRuntime>,
+        db: SharedConnection,
+        shared: SharedWorkerManager,
+        launcher: FakeLauncher,
+        project_id: String,
+    }
+
+    fn test_timings() -> WorkerTimings {
+        WorkerTimings {
+            command_ack_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Heartbeats are checked often, so ack timeouts expire promptly, but a
+    /// silent worker is only declared hung after several seconds.
+    fn test_policy() -> WorkerRestartPolicy {
+        WorkerRestartPolicy {
+            restart_backoff_ms: vec![10],
+            heartbeat_interval_ms: 20,
+            heartbeat_max_misses: u8::MAX,
+            ..WorkerRestartPolicy::default()
+        }
+    }
+
+    fn harness(timings: WorkerTimings) -> Harness {
+        let db = database::open_in_memory();
+        let project_id = {
+            let conn = db.lock().unwrap();
+            let input = CreateProjectInput {
+                name: "worker test".into(),
+                theme: None,
+                target_platform: None,
             };
-            if should_restart {
-                let _ = WorkerManager::restart(&shared, &app, &reason);
+            worker_settings_repo::set_worker_restart_policy(&conn, &test_policy()).unwrap();
+            projects_repo::create(&conn, input).unwrap().id
+        };
+        let media_dir = env::temp_dir().join(format!("dy-worker-test-{}", Uuid::new_v4()));
+        let media_store = MediaStore::open(media_dir).unwrap();
+        let launcher = FakeLauncher::new();
+        let shared = WorkerManager::new(
+            Arc::clone(&db),
+            media_store,
+            Arc::new(launcher.clone()),
+            timings,
+        );
+        let app = mock_app();
+        Harness {
+            app: app.handle().clone(),
+            _app: app,
+            db,
+            shared,
+            launcher,
+            project_id,
+        }
+    }
+
+    impl Harness {
+        fn state(&self) -> WorkerState {
+            WorkerManager::get_status(&self.shared).unwrap().state
+        }
+
+        fn wait_for_state(&self, state: WorkerState) {
+            let reached = wait_until(WAIT, || (self.state() == state).then_some(()));
+            assert!(
+                reached.is_some(),
+                "worker never became {state:?}, still {:?}",
+                self.state()
+            );
+        }
+
+        fn set_policy(&self, policy: WorkerRestartPolicy) {
+            WorkerManager::set_restart_policy(&self.shared, policy).unwrap();
+        }
+
+        /// Crashes every launch until the breaker opens, under a policy that
+        /// tolerates a single restart.
+        fn open_circuit(&self) {
+            self.set_policy(WorkerRestartPolicy {
+                circuit_breaker_max_restarts: 1,
+                ..test_policy()
+            });
+            WorkerManager::start(&self.shared, &self.app).unwrap();
+            for launch in 1..=2 {
+                let worker = self.launcher.wait_for_launch(launch, WAIT).unwrap();
+                worker.crash(1);
+            }
+            self.wait_for_state(WorkerState::CircuitBroken);
+        }
+
+        fn start_ready(&self) -> FakeWorker {
+            WorkerManager::start(&self.shared, &self.app).unwrap();
+            let worker = self.launcher.worker(self.launcher.launches() - 1);
+            worker.hello();
+            self.wait_for_state(WorkerState::Ready);
+            worker
+        }
+
+        /// Starts a pool laid out by `config` and says hello from every
+        /// worker in it, which are launched in pool order.
+        fn start_pool(&self, config: WorkerPoolConfig) -> Vec<FakeWorker> {
+            let size = config.slots().len();
+            WorkerManager::set_pool_config(&self.shared, config).unwrap();
+            WorkerManager::start(&self.shared, &self.app).unwrap();
+            let workers: Vec<FakeWorker> = (0..size).map(|i| self.launcher.worker(i)).collect();
+            for worker in &workers {
+                worker.hello();
             }
+            let ready = wait_until(WAIT, || {
+                let status = WorkerManager::get_status(&self.shared).unwrap();
+                let all_ready = status
+                    .workers
+                    .iter()
+                    .all(|worker| worker.state == WorkerState::Ready);
+                all_ready.then_some(())
+            });
+            assert!(ready.is_some(), "pool never became ready");
+            workers
+        }
+
+        fn submit(&self) -> String {
+            self.submit_type("tts")
+        }
+
+        fn submit_type(&self, task_type: &str) -> String {
+            let input = SubmitTaskInput {
+                task_type: task_type.into(),
+                project_id: self.project_id.clone(),
+                config: json!({}),
+                priority: TaskPriority::Normal,
+            };
+            WorkerManager::submit_task(&self.shared, &self.app, input).unwrap()
+        }
+
+        fn task(&self, task_id: &str) -> TaskInfo {
+            let conn = self.db.lock().unwrap();
+            tasks_repo::list_by_project(&conn, &self.project_id)
+                .unwrap()
+                .into_iter()
+                .find(|task| task.task_id == task_id)
+                .unwrap()
+        }
+
+        fn wait_for_task(&self, task_id: &str, status: TaskStatus) {
+            let reached = wait_until(WAIT, || (self.task(task_id).status == status).then_some(()));
+            assert!(
+                reached.is_some(),
+                "task never became {status:?}, still {:?}",
+                self.task(task_id).status
+            );
+        }
+
+        fn restarts(&self) -> Vec<RestartRecord> {
+            WorkerManager::diagnostics(&self.shared).unwrap().restarts
+        }
+    }
+
+    #[test]
+    fn hello_moves_worker_from_starting_to_ready() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        assert_eq!(h.state(), WorkerState::Starting);
+
+        let worker = h.launcher.worker(0);
+        worker.hello();
+        h.wait_for_state(WorkerState::Ready);
+
+        let welcome = worker.wait_for(EVENT_WORKER_WELCOME, WAIT).unwrap();
+        assert_eq!(welcome.payload["accepted"], json!(true));
+        let status = WorkerManager::get_status(&h.shared).unwrap();
+        assert_eq!(status.ipc_version.as_deref(), Some(IPC_VERSION));
+    }
+
+    #[test]
+    fn task_runs_through_busy_back_to_ready() {
+        let h = harness(test_timings());
+        let worker = h.start_ready();
+        let task_id = h.submit();
+
+        let submit = worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+        assert_eq!(submit.payload["task_id"], json!(task_id));
+        worker.ack(&submit);
+        worker.started(&task_id);
+        h.wait_for_state(WorkerState::Busy);
+        h.wait_for_task(&task_id, TaskStatus::Running);
+
+        worker.progress(&task_id, 0.5);
+        worker.complete(&task_id);
+        h.wait_for_task(&task_id, TaskStatus::Completed);
+        h.wait_for_state(WorkerState::Ready);
+    }
+
+    #[test]
+    fn failed_task_returns_worker_to_ready() {
+        let h = harness(test_timings());
+        let worker = h.start_ready();
+        let task_id = h.submit();
+        let submit = worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+        worker.ack(&submit);
+        worker.started(&task_id);
+        h.wait_for_state(WorkerState::Busy);
+
+        worker.stderr("Traceback (most recent call last): ...");
+        worker.fail(&task_id, "model returned nothing", None);
+
+        h.wait_for_task(&task_id, TaskStatus::Failed);
+        h.wait_for_state(WorkerState::Ready);
+        assert_eq!(h.launcher.launches(), 1);
+    }
+
+    #[test]
+    fn submission_for_unknown_project_is_refused() {
+        let h = harness(test_timings());
+        let input = SubmitTaskInput {
+            task_type: "echo".into(),
+            project_id: "missing".into(),
+            config: json!({}),
+            priority: TaskPriority::Normal,
+        };
+        let err = WorkerManager::submit_task(&h.shared, &h.app, input).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        let input = SubmitTaskGraphInput {
+            project_id: "missing".into(),
+            priority: TaskPriority::Normal,
+            nodes: vec![TaskGraphNodeInput {
+                key: "a".into(),
+                task_type: "echo".into(),
+                config: json!({}),
+                depends_on: Vec::new(),
+            }],
+        };
+        let err = WorkerManager::submit_task_graph(&h.shared, &h.app, input).unwrap_err();
+        assert_eq!(err.code, ErrorCode::NotFound);
+
+        // Nothing was queued, so the pool was never started.
+        assert_eq!(h.launcher.launches(), 0);
+        let conn = h.db.lock().unwrap();
+        assert!(tasks_repo::list_all(&conn).unwrap().is_empty());
+    }
+
+    #[test]
+    fn crash_restarts_worker_and_resubmits_in_flight_task() {
+        let h = harness(test_timings());
+        let first = h.start_ready();
+        let task_id = h.submit();
+        let submit = first.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+        first.ack(&submit);
+        first.started(&task_id);
+        h.wait_for_task(&task_id, TaskStatus::Running);
+
+        first.crash(1);
+        let second = h.launcher.wait_for_launch(2, WAIT).unwrap();
+        assert_eq!(h.state(), WorkerState::Starting);
+        second.hello();
+
+        let resubmit = second.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+        assert_eq!(resubmit.payload["task_id"], json!(task_id));
+        let restarts = h.restarts();
+        assert_eq!(restarts.len(), 1);
+        assert!(restarts[0].reason.contains("exited with code Some(1)"));
+        assert!(!restarts[0].circuit_broken);
+    }
+
+    #[test]
+    fn malformed_frame_kills_and_restarts_worker() {
+        let h = harness(test_timings());
+        let first = h.start_ready();
+
+        first.send_raw(b"{this is not json}\n");
+        h.launcher.wait_for_launch(2, WAIT).unwrap();
+
+        assert!(first.is_killed());
+        assert!(h.restarts()[0].reason.contains("invalid NDJSON frame"));
+    }
+
+    #[test]
+    fn partial_frames_are_buffered_until_complete() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        let worker = h.launcher.worker(0);
+
+        let hello = Envelope::new(
+            MessageKind::Event,
+            EVENT_WORKER_HELLO,
+            json!({ "ipc_versions": [IPC_VERSION] }),
+        );
+        let line = hello.to_ndjson_line().unwrap();
+        let (head, tail) = line.split_at(line.len() / 2);
+        worker.send_raw(head.as_bytes());
+        worker.send_raw(tail.as_bytes());
+
+        h.wait_for_state(WorkerState::Ready);
+        assert_eq!(h.launcher.launches(), 1);
+    }
+
+    #[test]
+    fn missed_heartbeats_restart_hung_worker() {
+        let h = harness(test_timings());
+        h.set_policy(WorkerRestartPolicy {
+            heartbeat_max_misses: 3,
+            ..test_policy()
         });
+        let first = h.start_ready();
+
+        // The worker says nothing more after its hello.
+        h.launcher.wait_for_launch(2, WAIT).unwrap();
+
+        assert!(first.is_killed());
+        assert_eq!(h.restarts()[0].reason, "heartbeat timeout");
     }
-}
 
-fn mark_unhealthy(shared: &SharedWorkerManager, session_id: u64) -> bool {
-    let mut m = match lock(shared) {
-        Ok(m) => m,
-        Err(_) => return false,
-    };
+    #[test]
+    fn worker_waits_for_hello_before_taking_tasks() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        let worker = h.launcher.worker(0);
+        let task_id = h.submit();
+
+        worker.heartbeat();
+        worker.started(&task_id);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(h.state(), WorkerState::Starting);
+        assert!(worker.submitted_tasks().is_empty());
+
+        worker.hello();
+        let dispatched = wait_until(WAIT, || {
+            worker.submitted_tasks().contains(&task_id).then_some(())
+        });
+        assert!(dispatched.is_some(), "task never dispatched");
+    }
+
+    #[test]
+    fn welcome_goes_out_before_queued_tasks() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        let worker = h.launcher.worker(0);
+        h.submit();
 
-    if m.session_id != session_id {
-        return false;
+        worker.hello();
+        worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+
+        let events: Vec<String> = worker.received().into_iter().map(|e| e.event).collect();
+        assert_eq!(events, [EVENT_WORKER_WELCOME, EVENT_TASK_SUBMIT]);
     }
 
-    if let Some(child) = m.child.take() {
-        let _ = child.kill();
+    #[test]
+    fn heartbeats_keep_worker_alive() {
+        let h = harness(test_timings());
+        h.set_policy(WorkerRestartPolicy {
+            heartbeat_max_misses: 3,
+            ..test_policy()
+        });
+        let worker = h.start_ready();
+
+        for _ in 0..40 {
+            worker.heartbeat();
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        assert_eq!(h.launcher.launches(), 1);
+        assert_eq!(h.state(), WorkerState::Ready);
+    }
+
+    #[test]
+    fn repeated_crashes_open_circuit_breaker() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+
+        let max_restarts = test_policy().circuit_breaker_max_restarts as usize;
+        for launch in 1..=max_restarts + 1 {
+            h.launcher.wait_for_launch(launch, WAIT).unwrap().crash(1);
+        }
+        h.wait_for_state(WorkerState::CircuitBroken);
+
+        assert_eq!(h.launcher.launches(), max_restarts + 1);
+        assert!(h.restarts().last().unwrap().circuit_broken);
+        let err = WorkerManager::start(&h.shared, &h.app).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WorkerUnavailable);
     }
-    if matches!(m.state, WorkerState::Stopped | WorkerState::CircuitBroken) {
-        return false;
+
+    #[test]
+    fn reset_circuit_lets_a_single_probe_through() {
+        let h = harness(test_timings());
+        assert!(!WorkerManager::reset_circuit(&h.shared, &h.app).unwrap());
+        h.open_circuit();
+
+        assert!(WorkerManager::reset_circuit(&h.shared, &h.app).unwrap());
+        assert_eq!(h.state(), WorkerState::HalfOpen);
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        h.launcher.wait_for_launch(3, WAIT).unwrap().crash(1);
+        h.wait_for_state(WorkerState::CircuitBroken);
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(h.launcher.launches(), 3);
+        assert!(h.restarts().last().unwrap().circuit_broken);
     }
 
-    m.state = WorkerState::Unhealthy;
-    true
-}
+    #[test]
+    fn successful_probe_closes_the_circuit() {
+        let h = harness(test_timings());
+        h.open_circuit();
+        WorkerManager::reset_circuit(&h.shared, &h.app).unwrap();
+
+        let probe = h.start_ready();
+        let status = WorkerManager::get_status(&h.shared).unwrap();
+        assert_eq!(status.restart_count_10m, 0);
+
+        // Back to the normal policy: one crash is restarted, not broken.
+        probe.crash(1);
+        h.launcher.wait_for_launch(4, WAIT).unwrap();
+        assert!(!h.restarts().last().unwrap().circuit_broken);
+    }
+
+    #[test]
+    fn heartbeats_alone_do_not_close_the_circuit() {
+        let h = harness(test_timings());
+        h.open_circuit();
+        WorkerManager::reset_circuit(&h.shared, &h.app).unwrap();
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        let probe = h.launcher.wait_for_launch(3, WAIT).unwrap();
+
+        for _ in 0..5 {
+            probe.heartbeat();
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(h.state(), WorkerState::Starting);
+        probe.crash(1);
+
+        h.wait_for_state(WorkerState::CircuitBroken);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(h.launcher.launches(), 3);
+    }
+
+    #[test]
+    fn failed_launch_leaves_worker_stopped() {
+        let h = harness(test_timings());
+        h.launcher.fail_next_launches(1);
+
+        let err = WorkerManager::start(&h.shared, &h.app).unwrap_err();
+
+        assert!(err.message.contains("no such file"));
+        assert_eq!(h.state(), WorkerState::Stopped);
+        h.start_ready();
+    }
+
+    #[test]
+    fn incompatible_hello_stops_worker_for_good() {
+        let h = harness(test_timings());
+        WorkerManager::start(&h.shared, &h.app).unwrap();
+        let worker = h.launcher.worker(0);
+
+        worker.hello_with(json!({ "ipc_versions": ["99.0"] }));
+        h.wait_for_state(WorkerState::Incompatible);
+
+        let welcome = worker.wait_for(EVENT_WORKER_WELCOME, WAIT).unwrap();
+        assert_eq!(welcome.payload["accepted"], json!(false));
+        assert!(wait_until(WAIT, || worker.is_killed().then_some(())).is_some());
+        let err = WorkerManager::start(&h.shared, &h.app).unwrap_err();
+        assert_eq!(err.code, ErrorCode::WorkerUnavailable);
+        assert_eq!(h.launcher.launches(), 1);
+    }
+
+    #[test]
+    fn foreign_ipc_major_after_handshake_stops_worker_for_good() {
+        let h = harness(test_timings());
+        let worker = h.start_ready();
+
+        worker.send(&Envelope {
+            v: "2.0".into(),
+            ..Envelope::new(MessageKind::Event, EVENT_WORKER_HEARTBEAT, json!({}))
+        });
+        h.wait_for_state(WorkerState::Incompatible);
+
+        assert!(wait_until(WAIT, || worker.is_killed().then_some(())).is_some());
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(h.launcher.launches(), 1);
+        assert!(h.restarts().is_empty());
+    }
+
+    #[test]
+    fn unacknowledged_submit_fails_task() {
+        let h = harness(WorkerTimings {
+            command_ack_timeout: Duration::from_millis(50),
+            ..test_timings()
+        });
+        let worker = h.start_ready();
+        let task_id = h.submit();
+        worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+
+        h.wait_for_task(&task_id, TaskStatus::Failed);
+        let error = h.task(&task_id).error.unwrap();
+        assert!(error.contains("did not acknowledge task.submit"));
+        let cancel = worker.wait_for(EVENT_TASK_CANCEL, WAIT).unwrap();
+        assert_eq!(cancel.payload["task_id"], json!(task_id));
+    }
+
+    #[test]
+    fn task_events_stand_in_for_a_missing_submit_ack() {
+        let h = harness(WorkerTimings {
+            command_ack_timeout: Duration::from_millis(50),
+            ..test_timings()
+        });
+        let worker = h.start_ready();
+        let task_id = h.submit();
+        worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+
+        worker.started(&task_id);
+        h.wait_for_task(&task_id, TaskStatus::Running);
+        thread::sleep(Duration::from_millis(150));
+        worker.complete(&task_id);
+
+        h.wait_for_task(&task_id, TaskStatus::Completed);
+        assert!(worker.wait_for(EVENT_TASK_CANCEL, Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn saved_launch_config_applies_on_next_start() {
+        let h = harness(test_timings());
+        let worker = h.start_ready();
+        let config = WorkerLaunchConfig {
+            program: WorkerProgram::Executable {
+                path: "/opt/dy-worker/target/debug/dy-worker".into(),
+            },
+            args: vec!["--log-level".into(), "debug".into()],
+            env: BTreeMap::from([("HF_HOME".into(), "/data/models".into())]),
+            working_dir: Some("/opt/dy-worker".into()),
+        };
+
+        WorkerManager::set_launch_config(&h.shared, config.clone()).unwrap();
+        assert!(!worker.is_killed());
+        WorkerManager::restart(&h.shared, &h.app, "launch config changed").unwrap();
+
+        assert_eq!(
+            h.launcher.configs(),
+            vec![WorkerLaunchConfig::default(), config.clone()]
+        );
+        let saved = worker_settings_repo::get_worker_launch_config(&h.db.lock().unwrap()).unwrap();
+        assert_eq!(saved, config);
+    }
+
+    #[test]
+    fn pool_hands_each_task_to_the_least_busy_worker() {
+        let h = harness(test_timings());
+        let workers = h.start_pool(WorkerPoolConfig {
+            size: 2,
+            groups: Vec::new(),
+        });
+
+        let first = h.submit();
+        let second = h.submit();
+
+        assert_eq!(workers[0].submitted_tasks(), vec![first]);
+        assert_eq!(workers[1].submitted_tasks(), vec![second]);
+        let status = WorkerManager::get_status(&h.shared).unwrap();
+        assert_eq!(status.state, WorkerState::Busy);
+        assert!(status
+            .workers
+            .iter()
+            .all(|worker| worker.state == WorkerState::Busy && worker.in_flight_tasks == 1));
+    }
+
+    #[test]
+    fn crash_hands_tasks_to_the_rest_of_the_pool() {
+        let h = harness(test_timings());
+        let workers = h.start_pool(WorkerPoolConfig {
+            size: 2,
+            groups: Vec::new(),
+        });
+        let lost = h.submit();
+        let kept = h.submit();
+        for worker in &workers {
+            let submit = worker.wait_for(EVENT_TASK_SUBMIT, WAIT).unwrap();
+            worker.ack(&submit);
+            worker.started(submit.payload["task_id"].as_str().unwrap());
+        }
+        h.wait_for_task(&lost, TaskStatus::Running);
+        h.wait_for_task(&kept, TaskStatus::Running);
+
+        workers[0].crash(1);
+        let handed_over = wait_until(WAIT, || {
+            workers[1].submitted_tasks().contains(&lost).then_some(())
+        });
+
+        assert!(handed_over.is_some(), "lost task was never resubmitted");
+        assert!(!workers[1].is_killed());
+        assert_eq!(h.task(&kept).status, TaskStatus::Running);
+        h.launcher.wait_for_launch(3, WAIT).unwrap();
+        let restarts = h.restarts();
+        assert_eq!(restarts.len(), 1);
+        assert_eq!(restarts[0].worker, 0);
+    }
 
-fn is_session_active(shared: &SharedWorkerManager, session_id: u64) -> bool {
-    lock(shared).is_ok_and(|m| m.session_id == session_id)
+    #[test]
+    fn grouped_task_types_only_run_in_their_group() {
+        let h = harness(test_timings());
+        let workers = h.start_pool(WorkerPoolConfig {
+            size: 1,
+            groups: vec![WorkerGroupConfig {
+                name: "render".into(),
+                task_types: vec!["render_video".into()],
+                size: 1,
+            }],
+        });
+
+        let render = h.submit_type("render_video");
+        let tts = h.submit_type("tts");
+
+        assert_eq!(workers[0].submitted_tasks(), vec![tts]);
+        assert_eq!(workers[1].submitted_tasks(), vec![render]);
+        let status = WorkerManager::get_status(&h.shared).unwrap();
+        assert_eq!(status.workers[0].group, None);
+        assert_eq!(status.workers[1].group.as_deref(), Some("render"));
+    }
+
+    #[test]
+    fn stop_kills_worker_without_restart() {
+        let h = harness(test_timings());
+        let worker = h.start_ready();
+
+        WorkerManager::stop(&h.shared, &h.app).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        assert!(worker.is_killed());
+        assert_eq!(h.state(), WorkerState::Stopped);
+        assert_eq!(h.launcher.launches(), 1);
+        assert!(h.restarts().is_empty());
+    }
 }
@@ -1,3 +1,9 @@
+pub mod commands;
 pub mod dispatcher;
 pub mod heartbeat;
+pub mod journal;
 pub mod manager;
+pub mod outputs;
+pub mod process;
+#[cfg(test)]
+pub mod fake;
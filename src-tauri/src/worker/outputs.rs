@@ -0,0 +1,132 @@
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    database::SharedConnection,
+    error::{AppError, AppResult},
+    ipc::protocol::OutputAssetRef,
+    media_store::{MediaStore, StoredFile},
+    models::{Asset, CreateAssetInput, TaskInfo},
+    repository::{assets_repo, scenes_repo},
+};
+
+const REGISTERED_ASSET_STATUS: &str = "ready";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AssetsRegisteredEvent {
+    pub task_id: String,
+    pub project_id: String,
+    pub trace_id: Option<String>,
+    pub assets: Vec<Asset>,
+}
+
+struct StoredOutput {
+    output: OutputAssetRef,
+    stored: StoredFile,
+}
+
+/// Copies every file listed under `output.assets` of a completed task into
+/// the media store and inserts an `assets` row for it. Entries with an
+/// unknown type or an unreadable path are skipped so one bad file does not
+/// discard the rest of the task's output.
+pub fn register_task_outputs(
+    db: &SharedConnection,
+    media_store: &MediaStore,
+    task: &TaskInfo,
+) -> AppResult<Vec<Asset>> {
+    if task.project_id.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(raw) = task.output.as_ref().and_then(|output| output.get("assets")) else {
+        return Ok(Vec::new());
+    };
+    let outputs: Vec<OutputAssetRef> = serde_json::from_value(raw.clone()).map_err(|e| {
+        AppError::worker(format!(
+            "invalid output.assets for task {}: {e}",
+            task.task_id
+        ))
+    })?;
+
+    // Copy before taking the database lock; generated videos can be large.
+    let stored: Vec<StoredOutput> = outputs
+        .into_iter()
+        .filter_map(|output| match store_output(media_store, &output) {
+            Ok(stored) => Some(StoredOutput { output, stored }),
+            Err(err) => {
+                tracing::warn!(
+                    task_id = %task.task_id,
+                    trace_id = task.trace_label(),
+                    path = %output.path,
+                    %err,
+                    "skipping task output"
+                );
+                None
+            }
+        })
+        .collect();
+    if stored.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let default_scene_id = task
+        .config
+        .get("scene_id")
+        .and_then(Value::as_str)
+        .map(str::to_owned);
+
+    let conn = db
+        .lock()
+        .map_err(|e| AppError::lock_poisoned("database", e))?;
+    let tx = conn.unchecked_transaction()?;
+
+    let mut assets = Vec::with_capacity(stored.len());
+    for StoredOutput { output, stored } in stored {
+        let scene_id = match output.scene_id.or_else(|| default_scene_id.clone()) {
+            Some(scene_id) => scenes_repo::get_by_id(&tx, &scene_id)?
+                .filter(|scene| scene.project_id == task.project_id)
+                .map(|scene| scene.id),
+            None => None,
+        };
+        let meta_json = output
+            .meta
+            .as_ref()
+            .and_then(|meta| serde_json::to_string(meta).ok());
+
+        let asset = assets_repo::create(
+            &tx,
+            CreateAssetInput {
+                project_id: task.project_id.clone(),
+                scene_id,
+                task_id: Some(task.task_id.clone()),
+                asset_type: output.asset_type,
+                provider: output.provider.or_else(|| Some(task.task_type.clone())),
+                local_path: Some(stored.relative_path),
+                meta_json,
+                checksum: Some(stored.checksum),
+                status: Some(REGISTERED_ASSET_STATUS.into()),
+            },
+        )?;
+        assets.push(asset);
+    }
+
+    tx.commit()?;
+    Ok(assets)
+}
+
+fn store_output(media_store: &MediaStore, output: &OutputAssetRef) -> Result<StoredFile, String> {
+    if !assets_repo::ASSET_TYPES.contains(&output.asset_type.as_str()) {
+        return Err(format!("unsupported asset type '{}'", output.asset_type));
+    }
+    let path = Path::new(&output.path);
+    if !path.is_absolute() {
+        return Err("path must be absolute".into());
+    }
+    if !path.is_file() {
+        return Err("path does not point to a file".into());
+    }
+    media_store
+        .import_file(path)
+        .map_err(|e| format!("failed to copy into media store: {e}"))
+}
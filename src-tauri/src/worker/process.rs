@@ -0,0 +1,147 @@
+use std::{
+    fmt,
+    fs::File,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_shell::{
+    process::{CommandChild, CommandEvent},
+    ShellExt,
+};
+
+use crate::{
+    logging,
+    models::{WorkerLaunchConfig, WorkerProgram},
+};
+
+/// Output of a worker process, as seen by the manager's event listener.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerEvent {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Error(String),
+    Terminated {
+        code: Option<i32>,
+        signal: Option<i32>,
+    },
+}
+
+/// The running worker's stdin and lifetime.
+pub trait WorkerProcess: Send + fmt::Debug {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String>;
+    fn kill(self: Box<Self>) -> Result<(), String>;
+}
+
+/// A freshly started worker. The event channel closes when the process's
+/// output streams do.
+pub struct LaunchedWorker {
+    pub events: Receiver<WorkerEvent>,
+    pub process: Box<dyn WorkerProcess>,
+    /// Where the session's stderr goes; `None` sends it to the app log.
+    pub stderr_log: Option<File>,
+}
+
+/// Starts worker processes. `WorkerManager` only talks to the worker through
+/// this, so tests can swap the sidecar for a scripted fake.
+pub trait WorkerLauncher: Send + Sync + fmt::Debug {
+    fn launch(
+        &self,
+        session_id: u64,
+        config: &WorkerLaunchConfig,
+    ) -> Result<LaunchedWorker, String>;
+}
+
+/// Launches the worker through the shell plugin, either as a bundled
+/// sidecar or as an executable on disk.
+pub struct ShellLauncher<R: Runtime> {
+    app: AppHandle<R>,
+}
+
+impl<R: Runtime> ShellLauncher<R> {
+    pub fn new(app: AppHandle<R>) -> Self {
+        Self { app }
+    }
+}
+
+impl<R: Runtime> fmt::Debug for ShellLauncher<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShellLauncher").finish_non_exhaustive()
+    }
+}
+
+impl<R: Runtime> WorkerLauncher for ShellLauncher<R> {
+    fn launch(
+        &self,
+        session_id: u64,
+        config: &WorkerLaunchConfig,
+    ) -> Result<LaunchedWorker, String> {
+        let mut command = match &config.program {
+            WorkerProgram::Sidecar { name } => self
+                .app
+                .shell()
+                .sidecar(name)
+                .map_err(|e| format!("failed to configure sidecar {name}: {e}"))?,
+            WorkerProgram::Executable { path } => self.app.shell().command(path),
+        }
+        .args(&config.args)
+        .envs(&config.env);
+        if let Some(dir) = &config.working_dir {
+            command = command.current_dir(dir);
+        }
+        let (mut receiver, child) = command
+            .spawn()
+            .map_err(|e| format!("failed to spawn {}: {e}", program_label(&config.program)))?;
+
+        // The shell plugin delivers events on an async channel; the listener
+        // reads a blocking one so fakes need no async runtime.
+        let (sender, events) = mpsc::channel();
+        thread::spawn(move || {
+            while let Some(event) = tauri::async_runtime::block_on(receiver.recv()) {
+                let event = match event {
+                    CommandEvent::Stdout(bytes) => WorkerEvent::Stdout(bytes),
+                    CommandEvent::Stderr(bytes) => WorkerEvent::Stderr(bytes),
+                    CommandEvent::Error(error) => WorkerEvent::Error(error),
+                    CommandEvent::Terminated(payload) => WorkerEvent::Terminated {
+                        code: payload.code,
+                        signal: payload.signal,
+                    },
+                    _ => continue,
+                };
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let stderr_log = logging::open_worker_session_log(&self.app, session_id)
+            .map_err(|err| tracing::warn!(session_id, %err, "worker stderr will not be captured"))
+            .ok();
+        Ok(LaunchedWorker {
+            events,
+            process: Box::new(ShellProcess(child)),
+            stderr_log,
+        })
+    }
+}
+
+fn program_label(program: &WorkerProgram) -> &str {
+    match program {
+        WorkerProgram::Sidecar { name } => name,
+        WorkerProgram::Executable { path } => path,
+    }
+}
+
+#[derive(Debug)]
+struct ShellProcess(CommandChild);
+
+impl WorkerProcess for ShellProcess {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.0.write(bytes).map_err(|e| e.to_string())
+    }
+
+    fn kill(self: Box<Self>) -> Result<(), String> {
+        self.0.kill().map_err(|e| e.to_string())
+    }
+}
@@ -15,9 +15,11 @@ const STATUS_BADGE: Record<
 > = {
   pending: { label: "排队", variant: "outline" },
   running: { label: "执行中", variant: "secondary" },
+  cancelling: { label: "取消中", variant: "warning" },
   completed: { label: "完成", variant: "success" },
   failed: { label: "失败", variant: "destructive" },
   cancelled: { label: "已取消", variant: "warning" },
+  interrupted: { label: "已中断", variant: "warning" },
 };
 
 const TASK_TYPE_LABELS: Record<string, string> = {
@@ -94,9 +96,15 @@ export function TaskQueuePanel({ className }: { className?: string }) {
   const tasks = useWorkerStore((s) => s.tasks);
 
   const { activeTasks, completedTasks } = useMemo(() => {
-    const active = tasks.filter((t) => t.status === "running" || t.status === "pending");
+    const active = tasks.filter(
+      (t) => t.status === "running" || t.status === "pending" || t.status === "cancelling",
+    );
     const completed = tasks.filter(
-      (t) => t.status === "completed" || t.status === "failed" || t.status === "cancelled",
+      (t) =>
+        t.status === "completed" ||
+        t.status === "failed" ||
+        t.status === "cancelled" ||
+        t.status === "interrupted",
     );
     return { activeTasks: active, completedTasks: completed };
   }, [tasks]);
@@ -14,6 +14,8 @@ const STATE_CONFIG: Record<
   unhealthy: { label: "异常", dotColor: "bg-orange-400", variant: "warning" },
   stopped: { label: "已停止", dotColor: "bg-zinc-400", variant: "outline" },
   circuit_broken: { label: "熔断", dotColor: "bg-red-500", variant: "destructive" },
+  half_open: { label: "熔断试探", dotColor: "bg-orange-400", variant: "warning" },
+  incompatible: { label: "版本不兼容", dotColor: "bg-red-500", variant: "destructive" },
 };
 
 export function WorkerStatusBadge({ className }: { className?: string }) {
@@ -30,15 +32,31 @@ export function WorkerStatusBadge({ className }: { className?: string }) {
       </TooltipTrigger>
       <TooltipContent side="bottom" className="text-xs">
         <p>Worker 状态: {config.label}</p>
+        {status.capabilities?.build && (
+          <p>Worker 版本: {status.capabilities.build.version}</p>
+        )}
+        {status.ipc_version && <p>IPC 协议: {status.ipc_version}</p>}
+        {status.incompatible_reason && <p>{status.incompatible_reason}</p>}
         {status.in_flight_tasks > 0 && (
           <p>进行中任务: {status.in_flight_tasks}</p>
         )}
+        {status.queued_tasks > 0 && (
+          <p>排队中任务: {status.queued_tasks}</p>
+        )}
         {status.restart_count_10m > 0 && (
           <p>近10分钟重启: {status.restart_count_10m}次</p>
         )}
         {status.last_heartbeat && (
           <p>最近心跳: {new Date(status.last_heartbeat).toLocaleTimeString()}</p>
         )}
+        {status.workers.length > 1 &&
+          status.workers.map((worker) => (
+            <p key={worker.index}>
+              Worker #{worker.index + 1}
+              {worker.group && ` (${worker.group})`}: {STATE_CONFIG[worker.state].label}
+              {worker.in_flight_tasks > 0 && `, ${worker.in_flight_tasks} 个任务`}
+            </p>
+          ))}
       </TooltipContent>
     </Tooltip>
   );
@@ -77,3 +77,13 @@ export function useStopWorker() {
     },
   });
 }
+
+export function useResetWorkerCircuit() {
+  const qc = useQueryClient();
+  return useMutation({
+    mutationFn: () => workerService.resetWorkerCircuit(),
+    onSuccess: () => {
+      qc.invalidateQueries({ queryKey: workerKeys.status });
+    },
+  });
+}
@@ -3,12 +3,23 @@ import type {
   Project,
   CreateProjectInput,
   UpdateProjectInput,
+  DuplicateProjectInput,
   ScriptVersion,
   CreateScriptVersionInput,
   StoryboardScene,
   CreateSceneInput,
   UpdateSceneInput,
   SceneReorderItem,
+  Asset,
+  CreateAssetInput,
+  UpdateAssetInput,
+  ImportAssetInput,
+  AssetGcInput,
+  AssetGcReport,
+  ProjectExportReport,
+  ProjectTemplate,
+  SaveProjectTemplateInput,
+  CreateProjectFromTemplateInput,
 } from "@/types/project";
 
 export async function createProject(input: CreateProjectInput): Promise<Project> {
@@ -34,6 +45,48 @@ export async function deleteProject(projectId: string): Promise<boolean> {
   return invoke<boolean>("delete_project", { projectId });
 }
 
+export async function duplicateProject(
+  projectId: string,
+  input: DuplicateProjectInput,
+): Promise<Project | null> {
+  return invoke<Project | null>("duplicate_project", { projectId, input });
+}
+
+export async function exportProject(
+  projectId: string,
+  destPath: string,
+): Promise<ProjectExportReport> {
+  return invoke<ProjectExportReport>("export_project", { projectId, destPath });
+}
+
+export async function importProject(bundlePath: string): Promise<Project> {
+  return invoke<Project>("import_project", { bundlePath });
+}
+
+export async function saveProjectAsTemplate(
+  input: SaveProjectTemplateInput,
+): Promise<ProjectTemplate | null> {
+  return invoke<ProjectTemplate | null>("save_project_as_template", { input });
+}
+
+export async function listProjectTemplates(): Promise<ProjectTemplate[]> {
+  return invoke<ProjectTemplate[]>("list_project_templates");
+}
+
+export async function getProjectTemplate(templateId: string): Promise<ProjectTemplate | null> {
+  return invoke<ProjectTemplate | null>("get_project_template", { templateId });
+}
+
+export async function createProjectFromTemplate(
+  input: CreateProjectFromTemplateInput,
+): Promise<Project | null> {
+  return invoke<Project | null>("create_project_from_template", { input });
+}
+
+export async function deleteProjectTemplate(templateId: string): Promise<boolean> {
+  return invoke<boolean>("delete_project_template", { templateId });
+}
+
 export async function createScriptVersion(
   input: CreateScriptVersionInput,
 ): Promise<ScriptVersion> {
@@ -73,3 +126,42 @@ export async function reorderScenes(
 ): Promise<StoryboardScene[]> {
   return invoke<StoryboardScene[]>("reorder_scenes", { projectId, items });
 }
+
+export async function createAsset(input: CreateAssetInput): Promise<Asset> {
+  return invoke<Asset>("create_asset", { input });
+}
+
+export async function listAssets(projectId: string): Promise<Asset[]> {
+  return invoke<Asset[]>("list_assets", { projectId });
+}
+
+export async function listSceneAssets(sceneId: string): Promise<Asset[]> {
+  return invoke<Asset[]>("list_scene_assets", { sceneId });
+}
+
+export async function getAsset(assetId: string): Promise<Asset | null> {
+  return invoke<Asset | null>("get_asset", { assetId });
+}
+
+export async function updateAsset(
+  assetId: string,
+  input: UpdateAssetInput,
+): Promise<Asset | null> {
+  return invoke<Asset | null>("update_asset", { assetId, input });
+}
+
+export async function deleteAsset(assetId: string): Promise<boolean> {
+  return invoke<boolean>("delete_asset", { assetId });
+}
+
+export async function importAsset(input: ImportAssetInput): Promise<Asset> {
+  return invoke<Asset>("import_asset", { input });
+}
+
+export async function resolveAssetPath(assetId: string): Promise<string | null> {
+  return invoke<string | null>("resolve_asset_path", { assetId });
+}
+
+export async function collectAssetGarbage(input: AssetGcInput): Promise<AssetGcReport> {
+  return invoke<AssetGcReport>("collect_asset_garbage", { input });
+}
@@ -1,5 +1,19 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { WorkerStatus, TaskInfo, SubmitTaskInput } from "@/types/worker";
+import type {
+  WorkerStatus,
+  TaskInfo,
+  SubmitTaskInput,
+  TaskPriority,
+  TaskRetryPolicy,
+  ConcurrencyLimits,
+  SubmitTaskGraphInput,
+  TaskGraphStatus,
+  TaskTimeouts,
+  DiagnosticsExportReport,
+  WorkerLaunchConfig,
+  WorkerPoolConfig,
+  WorkerRestartPolicy,
+} from "@/types/worker";
 
 export async function getWorkerStatus(): Promise<WorkerStatus> {
   return invoke<WorkerStatus>("get_worker_status");
@@ -13,8 +27,33 @@ export async function cancelTask(taskId: string): Promise<boolean> {
   return invoke<boolean>("cancel_task", { taskId });
 }
 
-export async function listTasks(): Promise<TaskInfo[]> {
-  return invoke<TaskInfo[]>("list_tasks");
+export async function submitTaskGraph(
+  input: SubmitTaskGraphInput,
+): Promise<TaskGraphStatus> {
+  return invoke<TaskGraphStatus>("submit_task_graph", { input });
+}
+
+export async function cancelTaskGraph(graphId: string): Promise<boolean> {
+  return invoke<boolean>("cancel_task_graph", { graphId });
+}
+
+export async function getTaskGraph(graphId: string): Promise<TaskGraphStatus | null> {
+  return invoke<TaskGraphStatus | null>("get_task_graph", { graphId });
+}
+
+export async function listTaskGraphs(projectId: string): Promise<TaskGraphStatus[]> {
+  return invoke<TaskGraphStatus[]>("list_task_graphs", { projectId });
+}
+
+export async function reprioritizeTask(
+  taskId: string,
+  priority: TaskPriority,
+): Promise<TaskInfo> {
+  return invoke<TaskInfo>("reprioritize_task", { taskId, priority });
+}
+
+export async function listTasks(projectId?: string): Promise<TaskInfo[]> {
+  return invoke<TaskInfo[]>("list_tasks", { projectId });
 }
 
 export async function startWorker(): Promise<void> {
@@ -24,3 +63,63 @@ export async function startWorker(): Promise<void> {
 export async function stopWorker(): Promise<void> {
   return invoke<void>("stop_worker");
 }
+
+export async function resetWorkerCircuit(): Promise<boolean> {
+  return invoke<boolean>("reset_worker_circuit");
+}
+
+export async function listRetryPolicies(): Promise<TaskRetryPolicy[]> {
+  return invoke<TaskRetryPolicy[]>("list_retry_policies");
+}
+
+export async function setRetryPolicy(input: TaskRetryPolicy): Promise<void> {
+  return invoke<void>("set_retry_policy", { input });
+}
+
+export async function deleteRetryPolicy(taskType: string): Promise<boolean> {
+  return invoke<boolean>("delete_retry_policy", { taskType });
+}
+
+export async function getConcurrencyLimits(): Promise<ConcurrencyLimits> {
+  return invoke<ConcurrencyLimits>("get_concurrency_limits");
+}
+
+export async function setConcurrencyLimits(limits: ConcurrencyLimits): Promise<void> {
+  return invoke<void>("set_concurrency_limits", { limits });
+}
+
+export async function getTaskTimeouts(): Promise<TaskTimeouts> {
+  return invoke<TaskTimeouts>("get_task_timeouts");
+}
+
+export async function setTaskTimeouts(timeouts: TaskTimeouts): Promise<void> {
+  return invoke<void>("set_task_timeouts", { timeouts });
+}
+
+export async function getWorkerLaunchConfig(): Promise<WorkerLaunchConfig> {
+  return invoke<WorkerLaunchConfig>("get_worker_launch_config");
+}
+
+export async function setWorkerLaunchConfig(config: WorkerLaunchConfig): Promise<void> {
+  return invoke<void>("set_worker_launch_config", { config });
+}
+
+export async function getWorkerPoolConfig(): Promise<WorkerPoolConfig> {
+  return invoke<WorkerPoolConfig>("get_worker_pool_config");
+}
+
+export async function setWorkerPoolConfig(config: WorkerPoolConfig): Promise<void> {
+  return invoke<void>("set_worker_pool_config", { config });
+}
+
+export async function getWorkerRestartPolicy(): Promise<WorkerRestartPolicy> {
+  return invoke<WorkerRestartPolicy>("get_worker_restart_policy");
+}
+
+export async function setWorkerRestartPolicy(policy: WorkerRestartPolicy): Promise<void> {
+  return invoke<void>("set_worker_restart_policy", { policy });
+}
+
+export async function exportDiagnostics(destPath: string): Promise<DiagnosticsExportReport> {
+  return invoke<DiagnosticsExportReport>("export_diagnostics", { destPath });
+}
@@ -15,6 +15,11 @@ const INITIAL_STATUS: WorkerStatus = {
   last_heartbeat: null,
   restart_count_10m: 0,
   in_flight_tasks: 0,
+  queued_tasks: 0,
+  capabilities: null,
+  ipc_version: null,
+  incompatible_reason: null,
+  workers: [],
 };
 
 /** Merge a single task into the list, keeping the newer version by updated_at */
@@ -84,7 +89,15 @@ export async function startWorkerEventListeners(): Promise<void> {
     upsertTask(event.payload);
   });
 
-  unlisteners = [u1, u2, u3, u4];
+  const u5 = await listen<TaskInfo>("task:retry_scheduled", (event) => {
+    upsertTask(event.payload);
+  });
+
+  const u6 = await listen<TaskInfo>("task:cancelled", (event) => {
+    upsertTask(event.payload);
+  });
+
+  unlisteners = [u1, u2, u3, u4, u5, u6];
 }
 
 export async function stopWorkerEventListeners(): Promise<void> {
@@ -0,0 +1,28 @@
+export type ErrorCode =
+  | "not_found"
+  | "validation"
+  | "constraint"
+  | "database"
+  | "lock_poisoned"
+  | "unsupported_format"
+  | "worker_not_running"
+  | "worker_unavailable"
+  | "worker"
+  | "io"
+  | "internal";
+
+/** Error payload rejected by every Tauri command. */
+export interface AppError {
+  code: ErrorCode;
+  message: string;
+  field?: string;
+}
+
+export function isAppError(value: unknown): value is AppError {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as AppError).code === "string" &&
+    typeof (value as AppError).message === "string"
+  );
+}
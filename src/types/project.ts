@@ -23,6 +23,47 @@ export interface UpdateProjectInput {
   status?: ProjectStatus | null;
 }
 
+export interface DuplicateProjectInput {
+  name?: string | null;
+  share_assets: boolean;
+}
+
+export interface TemplateScene {
+  scene_text: string;
+  visual_prompt: string | null;
+  duration_ms: number;
+  camera_hint: string | null;
+}
+
+export interface ProjectTemplate {
+  id: string;
+  name: string;
+  description: string | null;
+  category: string | null;
+  theme: string | null;
+  target_platform: string;
+  scenes: TemplateScene[];
+  prompt_snippets: string[];
+  is_builtin: boolean;
+  created_at: string;
+  updated_at: string;
+}
+
+export interface SaveProjectTemplateInput {
+  project_id: string;
+  name: string;
+  description?: string | null;
+  category?: string | null;
+  prompt_snippets?: string[] | null;
+}
+
+export interface CreateProjectFromTemplateInput {
+  template_id: string;
+  name: string;
+  theme?: string | null;
+  target_platform?: string | null;
+}
+
 export interface ScriptVersion {
   id: string;
   project_id: string;
@@ -74,3 +115,78 @@ export interface SceneReorderItem {
   id: string;
   scene_index: number;
 }
+
+export type AssetType = "image" | "video" | "audio" | "subtitle";
+
+export interface Asset {
+  id: string;
+  project_id: string;
+  scene_id: string | null;
+  task_id: string | null;
+  asset_type: AssetType;
+  provider: string | null;
+  local_path: string | null;
+  meta_json: string | null;
+  checksum: string | null;
+  status: string;
+}
+
+export interface CreateAssetInput {
+  project_id: string;
+  scene_id?: string | null;
+  task_id?: string | null;
+  asset_type: AssetType;
+  provider?: string | null;
+  local_path?: string | null;
+  meta_json?: string | null;
+  checksum?: string | null;
+  status?: string | null;
+}
+
+export interface UpdateAssetInput {
+  scene_id?: string | null;
+  provider?: string | null;
+  local_path?: string | null;
+  meta_json?: string | null;
+  checksum?: string | null;
+  status?: string | null;
+}
+
+export interface ImportAssetInput {
+  project_id: string;
+  scene_id?: string | null;
+  asset_type: AssetType;
+  source_path: string;
+  provider?: string | null;
+  meta_json?: string | null;
+}
+
+export interface AssetGcInput {
+  dry_run: boolean;
+  purge_sceneless: boolean;
+}
+
+export interface StoredFileEntry {
+  relative_path: string;
+  size_bytes: number;
+}
+
+export interface AssetGcReport {
+  dry_run: boolean;
+  unreferenced_files: StoredFileEntry[];
+  missing_file_assets: Asset[];
+  sceneless_assets: Asset[];
+  reclaimable_bytes: number;
+  deleted_files: number;
+  deleted_assets: number;
+}
+
+export interface ProjectExportReport {
+  path: string;
+  format_version: number;
+  script_versions: number;
+  scenes: number;
+  assets: number;
+  media_files: number;
+  missing_media_files: number;
+}
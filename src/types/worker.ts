@@ -1,40 +1,218 @@
+import type { Asset } from "@/types/project";
+
 export type WorkerState =
   | "starting"
   | "ready"
   | "busy"
   | "unhealthy"
   | "stopped"
-  | "circuit_broken";
+  | "circuit_broken"
+  | "half_open"
+  | "incompatible";
 
 export type TaskStatus =
   | "pending"
   | "running"
+  | "cancelling"
   | "completed"
   | "failed"
-  | "cancelled";
+  | "cancelled"
+  | "interrupted";
 
 export interface WorkerStatus {
   state: WorkerState;
   last_heartbeat: string | null;
   restart_count_10m: number;
   in_flight_tasks: number;
+  queued_tasks: number;
+  capabilities: WorkerCapabilities | null;
+  ipc_version: string | null;
+  incompatible_reason: string | null;
+  workers: WorkerSlotStatus[];
+}
+
+/** One process of the worker pool. */
+export interface WorkerSlotStatus {
+  index: number;
+  /** Task-type group the worker serves; null for the general pool. */
+  group: string | null;
+  state: WorkerState;
+  session_id: number;
+  last_heartbeat: string | null;
+  restart_count_10m: number;
+  in_flight_tasks: number;
+  ipc_version: string | null;
+  incompatible_reason: string | null;
 }
 
+/** Declared by the worker in `worker.hello`; an empty list means "not declared". */
+export interface WorkerCapabilities {
+  ipc_versions: string[];
+  task_types: TaskTypeCapability[];
+  max_concurrency: number | null;
+  build: WorkerBuildInfo | null;
+}
+
+export interface TaskTypeCapability {
+  task_type: string;
+  config_schema: Record<string, unknown> | null;
+}
+
+export interface WorkerBuildInfo {
+  version: string;
+  commit: string | null;
+  built_at: string | null;
+}
+
+export type TaskPriority = "low" | "normal" | "high";
+
 export interface TaskInfo {
   task_id: string;
   task_type: string;
   project_id: string;
   status: TaskStatus;
+  priority: TaskPriority;
   progress: number;
   message: string | null;
+  config: Record<string, unknown> | null;
   output: unknown | null;
   error: string | null;
+  graph: TaskGraphLink | null;
+  /** Shared by every task started by the same user action. */
+  trace_id: string | null;
+  attempt: number;
   created_at: string;
   updated_at: string;
 }
 
+export interface TaskGraphLink {
+  graph_id: string;
+  node_key: string;
+  depends_on: string[];
+}
+
+export type TaskGraphState =
+  | "pending"
+  | "running"
+  | "completed"
+  | "failed"
+  | "cancelled";
+
+export interface TaskGraphStatus {
+  graph_id: string;
+  project_id: string;
+  state: TaskGraphState;
+  progress: number;
+  nodes: TaskInfo[];
+  created_at: string;
+}
+
+export type ErrorClass =
+  | "worker_lost"
+  | "timeout"
+  | "rate_limited"
+  | "network"
+  | "failed";
+
+export interface RetryPolicy {
+  max_attempts: number;
+  initial_backoff_ms: number;
+  max_backoff_ms: number;
+  retry_on: ErrorClass[];
+}
+
+export interface TaskRetryPolicy {
+  task_type: string;
+  policy: RetryPolicy;
+}
+
 export interface SubmitTaskInput {
   task_type: string;
   project_id: string;
   config?: Record<string, unknown>;
+  priority?: TaskPriority;
+}
+
+/** A released node receives its dependencies' outputs as `config.upstream[key]`. */
+export interface TaskGraphNodeInput {
+  key: string;
+  task_type: string;
+  config?: Record<string, unknown>;
+  depends_on?: string[];
+}
+
+export interface SubmitTaskGraphInput {
+  project_id: string;
+  priority?: TaskPriority;
+  nodes: TaskGraphNodeInput[];
+}
+
+/** Watchdog limits in seconds; `null` disables a check. */
+export interface TaskTimeout {
+  max_runtime_secs: number | null;
+  stall_secs: number | null;
+}
+
+export interface TaskTimeouts {
+  default: TaskTimeout;
+  per_task_type: Record<string, TaskTimeout>;
+}
+
+export type WorkerProgram =
+  | { kind: "sidecar"; name: string }
+  | { kind: "executable"; path: string };
+
+/** How the worker is started; saved changes apply on the next start. */
+export interface WorkerLaunchConfig {
+  program: WorkerProgram;
+  args: string[];
+  env: Record<string, string>;
+  working_dir: string | null;
+}
+
+/** Restart backoff, circuit breaker and heartbeat limits; saved changes apply right away. */
+export interface WorkerRestartPolicy {
+  /** Delay before each consecutive restart; the last one repeats. */
+  restart_backoff_ms: number[];
+  circuit_breaker_window_secs: number;
+  /** Restarts tolerated within the window before the breaker opens. */
+  circuit_breaker_max_restarts: number;
+  heartbeat_interval_ms: number;
+  heartbeat_max_misses: number;
+}
+
+/** Layout of the worker pool; saved changes apply on the next start. */
+export interface WorkerPoolConfig {
+  /** Workers serving every task type not routed to a group. */
+  size: number;
+  groups: WorkerGroupConfig[];
+}
+
+/** Workers dedicated to a set of task types. */
+export interface WorkerGroupConfig {
+  name: string;
+  task_types: string[];
+  size: number;
+}
+
+export interface ConcurrencyLimits {
+  max_concurrent: number;
+  per_task_type: Record<string, number>;
+}
+
+/** Payload of the `asset:registered` event emitted after a task's output files are stored. */
+export interface AssetsRegisteredEvent {
+  task_id: string;
+  project_id: string;
+  trace_id: string | null;
+  assets: Asset[];
+}
+
+/** Summary of an archive written by `export_diagnostics`. */
+export interface DiagnosticsExportReport {
+  path: string;
+  log_files: number;
+  restarts: number;
+  envelopes: number;
+  database_intact: boolean;
 }